@@ -0,0 +1,254 @@
+use crate::commit::{CommitError, CommitReveal, Commitment, Contribution};
+use rand::{rngs::OsRng, RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
+use schnorrkel::PublicKey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+pub type RoundInput = [u8; 32];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeaconError {
+    Commit(CommitError),
+    // The beacon has not published a value for this round (yet).
+    MissingRound(u32),
+    // A line of a beacon file that is not `<round> <64 hex digits>`, numbered from 1.
+    InvalidEntry(usize),
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for BeaconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BeaconError::Commit(err) => write!(f, "{}", err),
+            BeaconError::MissingRound(round) => write!(f, "no beacon value for round {}", round),
+            BeaconError::InvalidEntry(line) => write!(f, "invalid beacon entry on line {}", line),
+            BeaconError::Io(kind) => write!(f, "beacon file error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for BeaconError {}
+
+impl From<CommitError> for BeaconError {
+    fn from(err: CommitError) -> Self {
+        BeaconError::Commit(err)
+    }
+}
+
+impl From<std::io::Error> for BeaconError {
+    fn from(err: std::io::Error) -> Self {
+        BeaconError::Io(err.kind())
+    }
+}
+
+// Where a table gets the shared input every VRF draw of a round is bound to.
+pub trait RandomnessBeacon: fmt::Debug {
+    fn round_input(&mut self, round: u32) -> Result<RoundInput, BeaconError>;
+}
+
+// Fresh randomness from the operating system. Only as trustworthy as whoever runs it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalBeacon;
+
+impl RandomnessBeacon for LocalBeacon {
+    fn round_input(&mut self, _round: u32) -> Result<RoundInput, BeaconError> {
+        let mut input = [0u8; 32];
+        OsRng.fill_bytes(&mut input);
+        Ok(input)
+    }
+}
+
+// The same inputs, in the same order, for the same seed. Meant for tests and replays.
+#[derive(Debug, Clone)]
+pub struct SeededBeacon {
+    rng: ChaChaRng,
+}
+
+impl SeededBeacon {
+    pub fn new(seed: [u8; 32]) -> Self {
+        SeededBeacon {
+            rng: ChaChaRng::from_seed(seed),
+        }
+    }
+}
+
+impl RandomnessBeacon for SeededBeacon {
+    fn round_input(&mut self, _round: u32) -> Result<RoundInput, BeaconError> {
+        let mut input = [0u8; 32];
+        self.rng.fill_bytes(&mut input);
+        Ok(input)
+    }
+}
+
+// Commit-reveal among the players. Commitments and reveals are fed in as they arrive,
+// from wherever the players are; the round input is available once everyone revealed,
+// after which a new session starts for the next round.
+#[derive(Debug, Clone)]
+pub struct CommitRevealBeacon {
+    session: CommitReveal,
+}
+
+impl CommitRevealBeacon {
+    pub fn new(participants: Vec<PublicKey>) -> Result<Self, BeaconError> {
+        Ok(CommitRevealBeacon {
+            session: CommitReveal::new(participants)?,
+        })
+    }
+
+    pub fn session(&self) -> &CommitReveal {
+        &self.session
+    }
+
+    // Abandons the current round, e.g. after someone failed to reveal.
+    pub fn reset(&mut self) {
+        self.session.reset();
+    }
+
+    pub fn commit(
+        &mut self,
+        public: &PublicKey,
+        commitment: Commitment,
+    ) -> Result<(), CommitError> {
+        self.session.commit(public, commitment)
+    }
+
+    pub fn reveal(
+        &mut self,
+        public: &PublicKey,
+        contribution: Contribution,
+    ) -> Result<(), CommitError> {
+        self.session.reveal(public, contribution)
+    }
+}
+
+impl RandomnessBeacon for CommitRevealBeacon {
+    fn round_input(&mut self, _round: u32) -> Result<RoundInput, BeaconError> {
+        let input = self.session.finish()?;
+        self.session.reset();
+        Ok(input)
+    }
+}
+
+// An external beacon, such as drand, whose values are written to a file as lines of
+// `<round> <64 hex digits>`. The file is read on every request, so it can be appended to
+// while the game runs. Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone)]
+pub struct FileBeacon {
+    path: PathBuf,
+}
+
+impl FileBeacon {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileBeacon { path: path.into() }
+    }
+
+    pub fn parse(text: &str) -> Result<BTreeMap<u32, RoundInput>, BeaconError> {
+        let mut entries = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = BeaconError::InvalidEntry(index + 1);
+            let (round, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or(invalid.clone())?;
+            let round: u32 = round.parse().map_err(|_| invalid.clone())?;
+            let mut input = [0u8; 32];
+            hex::decode_to_slice(value.trim(), &mut input).map_err(|_| invalid)?;
+            entries.insert(round, input);
+        }
+        Ok(entries)
+    }
+}
+
+impl RandomnessBeacon for FileBeacon {
+    fn round_input(&mut self, round: u32) -> Result<RoundInput, BeaconError> {
+        let entries = Self::parse(&std::fs::read_to_string(&self.path)?)?;
+        entries
+            .get(&round)
+            .copied()
+            .ok_or(BeaconError::MissingRound(round))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::commit;
+    use crate::player::Player;
+
+    #[test]
+    fn test_local_and_seeded() {
+        let mut local = LocalBeacon;
+        assert_ne!(local.round_input(1), local.round_input(1));
+
+        let mut first = SeededBeacon::new([7; 32]);
+        let mut second = SeededBeacon::new([7; 32]);
+        let inputs: Vec<_> = (1..4).map(|round| first.round_input(round)).collect();
+        assert_eq!(
+            inputs,
+            (1..4)
+                .map(|round| second.round_input(round))
+                .collect::<Vec<_>>()
+        );
+        assert_ne!(inputs[0], inputs[1]);
+        assert_ne!(SeededBeacon::new([8; 32]).round_input(1), inputs[0]);
+    }
+
+    #[test]
+    fn test_commit_reveal_beacon() {
+        let players = [Player::new(), Player::new()];
+        let publics: Vec<PublicKey> = players.iter().map(Player::public_key).collect();
+        let mut beacon = CommitRevealBeacon::new(publics.clone()).unwrap();
+        let contributions = [[1u8; 32], [2u8; 32]];
+
+        for round in 1..3 {
+            for (public, contribution) in publics.iter().zip(&contributions) {
+                beacon.commit(public, commit(public, contribution)).unwrap();
+            }
+            beacon.reveal(&publics[0], contributions[0]).unwrap();
+            assert_eq!(
+                beacon.round_input(round),
+                Err(BeaconError::Commit(CommitError::MissingReveals(vec![1])))
+            );
+            beacon.reveal(&publics[1], contributions[1]).unwrap();
+            assert!(beacon.round_input(round).is_ok());
+            // The next round starts from scratch.
+            assert!(!beacon.session().all_committed());
+        }
+    }
+
+    #[test]
+    fn test_file_beacon() {
+        let path = std::env::temp_dir().join(format!(
+            "vrf-poker-beacon-{}.txt",
+            hex::encode(LocalBeacon.round_input(0).unwrap())
+        ));
+        let mut beacon = FileBeacon::new(&path);
+        assert!(matches!(
+            beacon.round_input(1),
+            Err(BeaconError::Io(std::io::ErrorKind::NotFound))
+        ));
+
+        let text = format!("# drand\n1 {}\n\n2 {}\n", "ab".repeat(32), "cd".repeat(32));
+        std::fs::write(&path, &text).unwrap();
+        assert_eq!(beacon.round_input(2), Ok([0xcd; 32]));
+        assert_eq!(beacon.round_input(3), Err(BeaconError::MissingRound(3)));
+
+        std::fs::write(&path, format!("{}3 {}\n", text, "ef".repeat(32))).unwrap();
+        assert_eq!(beacon.round_input(3), Ok([0xef; 32]));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            FileBeacon::parse("1 abcd\n"),
+            Err(BeaconError::InvalidEntry(1))
+        );
+        assert_eq!(
+            FileBeacon::parse(&format!("\nfirst {}", "ab".repeat(32))),
+            Err(BeaconError::InvalidEntry(2))
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub type Chips = u64;
+
+// Amounts are totals for the current street: `Bet(20)` and `Raise(60)` both mean "make
+// my bet on this street 20 (or 60) chips in total", not "add this many chips".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    Bet(Chips),
+    Raise(Chips),
+    AllIn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseActionError(String);
+
+impl fmt::Display for ParseActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid action {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseActionError {}
+
+// Parses what a player would type: `fold`, `check`, `call`, `bet 20`, `raise 60` or `allin`.
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseActionError(s.to_string());
+        let words: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words[..] {
+            ["fold"] => Ok(Action::Fold),
+            ["check"] => Ok(Action::Check),
+            ["call"] => Ok(Action::Call),
+            ["allin"] | ["all", "in"] | ["all-in"] => Ok(Action::AllIn),
+            ["bet", amount] => amount.parse().map(Action::Bet).map_err(|_| error()),
+            ["raise", amount] => amount.parse().map(Action::Raise).map_err(|_| error()),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BettingError {
+    NotYourTurn,
+    RoundComplete,
+    RoundNotComplete,
+    CannotCheck,
+    NothingToCall,
+    BetAlreadyMade,
+    NoBetToRaise,
+    BelowMinimum { minimum: Chips },
+    InsufficientChips,
+    // Only an all-in for less than a full raise happened since this player last acted.
+    RaiseNotReopened,
+    TooFewSeats,
+    EmptyStack,
+    DealerNotSeated,
+    // The big blind must be at least one chip and no smaller than the small blind.
+    InvalidBlinds,
+}
+
+impl fmt::Display for BettingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BettingError::NotYourTurn => write!(f, "it is not this player's turn"),
+            BettingError::RoundComplete => write!(f, "the betting round is already complete"),
+            BettingError::RoundNotComplete => write!(f, "the betting round is not complete"),
+            BettingError::CannotCheck => write!(f, "cannot check facing a bet"),
+            BettingError::NothingToCall => write!(f, "there is no bet to call"),
+            BettingError::BetAlreadyMade => write!(f, "a bet has already been made, raise instead"),
+            BettingError::NoBetToRaise => write!(f, "there is no bet to raise, bet instead"),
+            BettingError::BelowMinimum { minimum } => {
+                write!(f, "must bet or raise to at least {}", minimum)
+            }
+            BettingError::InsufficientChips => write!(f, "not enough chips"),
+            BettingError::RaiseNotReopened => {
+                write!(f, "betting has not been reopened for a raise")
+            }
+            BettingError::TooFewSeats => write!(f, "a hand needs at least two players"),
+            BettingError::EmptyStack => write!(f, "every player needs chips"),
+            BettingError::DealerNotSeated => write!(f, "the dealer must be seated"),
+            BettingError::InvalidBlinds => {
+                write!(
+                    f,
+                    "the big blind must be positive and at least the small blind"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BettingError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatState {
+    pub stack: Chips,
+    // Chips put in on the current street.
+    pub street_bet: Chips,
+    // Chips put in over the whole hand, used to build the pots.
+    pub total_bet: Chips,
+    pub folded: bool,
+    pub all_in: bool,
+}
+
+impl SeatState {
+    // Still in the hand and able to make decisions.
+    pub fn is_active(&self) -> bool {
+        !self.folded && !self.all_in
+    }
+}
+
+// The betting for one hand of no-limit poker. Blinds are posted when the hand starts,
+// seats act in turn until everyone still able to act has matched the highest bet, and
+// `next_street` opens the following round of betting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Betting {
+    seats: Vec<SeatState>,
+    dealer: usize,
+    big_blind: Chips,
+    current_bet: Chips,
+    // Smallest legal raise increment: the big blind, or the size of the last full raise.
+    min_raise: Chips,
+    to_act: Option<usize>,
+    needs_to_act: Vec<bool>,
+    can_raise: Vec<bool>,
+}
+
+// Checks that a table's blinds can start a hand.
+pub fn check_blinds(small_blind: Chips, big_blind: Chips) -> Result<(), BettingError> {
+    if big_blind == 0 || small_blind > big_blind {
+        return Err(BettingError::InvalidBlinds);
+    }
+    Ok(())
+}
+
+impl Betting {
+    // Starts a hand with the button at `dealer`. Every stack must hold chips. Heads-up the
+    // dealer posts the small blind and acts first before the flop.
+    pub fn new(
+        stacks: &[Chips],
+        dealer: usize,
+        small_blind: Chips,
+        big_blind: Chips,
+    ) -> Result<Self, BettingError> {
+        if stacks.len() < 2 {
+            return Err(BettingError::TooFewSeats);
+        }
+        if stacks.contains(&0) {
+            return Err(BettingError::EmptyStack);
+        }
+        if dealer >= stacks.len() {
+            return Err(BettingError::DealerNotSeated);
+        }
+        check_blinds(small_blind, big_blind)?;
+
+        let n = stacks.len();
+        let mut betting = Betting {
+            seats: stacks
+                .iter()
+                .map(|&stack| SeatState {
+                    stack,
+                    street_bet: 0,
+                    total_bet: 0,
+                    folded: false,
+                    all_in: false,
+                })
+                .collect(),
+            dealer,
+            big_blind,
+            current_bet: 0,
+            min_raise: big_blind,
+            to_act: None,
+            needs_to_act: vec![true; n],
+            can_raise: vec![true; n],
+        };
+
+        let (small, big) = if n == 2 {
+            (dealer, (dealer + 1) % n)
+        } else {
+            ((dealer + 1) % n, (dealer + 2) % n)
+        };
+        betting.put_in(small, small_blind);
+        betting.put_in(big, big_blind);
+        betting.current_bet = betting
+            .seats
+            .iter()
+            .map(|seat| seat.street_bet)
+            .max()
+            .unwrap_or(0);
+        betting.advance(big);
+        Ok(betting)
+    }
+
+    pub fn seats(&self) -> &[SeatState] {
+        &self.seats
+    }
+
+    pub fn stacks(&self) -> Vec<Chips> {
+        self.seats.iter().map(|seat| seat.stack).collect()
+    }
+
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+
+    pub fn to_act(&self) -> Option<usize> {
+        self.to_act
+    }
+
+    pub fn current_bet(&self) -> Chips {
+        self.current_bet
+    }
+
+    // The smallest total a bet or full raise can go to right now.
+    pub fn min_raise_to(&self) -> Chips {
+        self.current_bet + self.min_raise
+    }
+
+    pub fn amount_to_call(&self, seat: usize) -> Chips {
+        let seat = &self.seats[seat];
+        (self.current_bet - seat.street_bet).min(seat.stack)
+    }
+
+    pub fn pot(&self) -> Chips {
+        self.seats.iter().map(|seat| seat.total_bet).sum()
+    }
+
+    pub fn is_round_complete(&self) -> bool {
+        self.to_act.is_none()
+    }
+
+    // Only one player has not folded, so they take the pot without a showdown.
+    pub fn is_hand_over(&self) -> bool {
+        self.seats.iter().filter(|seat| !seat.folded).count() <= 1
+    }
+
+    pub fn act(&mut self, seat: usize, action: Action) -> Result<(), BettingError> {
+        match self.to_act {
+            None => return Err(BettingError::RoundComplete),
+            Some(to_act) if to_act != seat => return Err(BettingError::NotYourTurn),
+            _ => {}
+        }
+
+        let state = &self.seats[seat];
+        let to_call = self.current_bet - state.street_bet;
+        match action {
+            Action::Fold => self.seats[seat].folded = true,
+            Action::Check if to_call > 0 => return Err(BettingError::CannotCheck),
+            Action::Check => {}
+            Action::Call if to_call == 0 => return Err(BettingError::NothingToCall),
+            Action::Call => self.put_in(seat, to_call),
+            Action::Bet(_) if self.current_bet > 0 => return Err(BettingError::BetAlreadyMade),
+            Action::Raise(_) if self.current_bet == 0 => return Err(BettingError::NoBetToRaise),
+            Action::Bet(to) | Action::Raise(to) => self.raise_to(seat, to)?,
+            Action::AllIn => {
+                let all_in_to = state.street_bet + state.stack;
+                if all_in_to > self.current_bet {
+                    self.raise_to(seat, all_in_to)?;
+                } else {
+                    self.put_in(seat, state.stack);
+                }
+            }
+        }
+
+        self.needs_to_act[seat] = false;
+        self.advance(seat);
+        Ok(())
+    }
+
+    // Opens betting on the next street once the current one is complete.
+    pub fn next_street(&mut self) -> Result<(), BettingError> {
+        if !self.is_round_complete() {
+            return Err(BettingError::RoundNotComplete);
+        }
+        for seat in self.seats.iter_mut() {
+            seat.street_bet = 0;
+        }
+        self.current_bet = 0;
+        self.min_raise = self.big_blind;
+        self.needs_to_act = self.seats.iter().map(SeatState::is_active).collect();
+        self.can_raise = vec![true; self.seats.len()];
+        self.advance(self.dealer);
+        Ok(())
+    }
+
+    fn put_in(&mut self, seat: usize, amount: Chips) {
+        let seat = &mut self.seats[seat];
+        let amount = amount.min(seat.stack);
+        seat.stack -= amount;
+        seat.street_bet += amount;
+        seat.total_bet += amount;
+        if seat.stack == 0 {
+            seat.all_in = true;
+        }
+    }
+
+    fn raise_to(&mut self, seat: usize, to: Chips) -> Result<(), BettingError> {
+        let state = &self.seats[seat];
+        let all_in_to = state.street_bet + state.stack;
+        if to > all_in_to {
+            return Err(BettingError::InsufficientChips);
+        }
+        if !self.can_raise[seat] {
+            return Err(BettingError::RaiseNotReopened);
+        }
+        let full_raise = to >= self.min_raise_to();
+        if !full_raise && to != all_in_to || to <= self.current_bet {
+            return Err(BettingError::BelowMinimum {
+                minimum: self.min_raise_to(),
+            });
+        }
+
+        self.put_in(seat, to - state.street_bet);
+        for other in 0..self.seats.len() {
+            if other == seat || !self.seats[other].is_active() {
+                continue;
+            }
+            if full_raise {
+                self.needs_to_act[other] = true;
+                self.can_raise[other] = true;
+            } else if !self.needs_to_act[other] {
+                // A short all-in only lets players who already acted call or fold.
+                self.needs_to_act[other] = true;
+                self.can_raise[other] = false;
+            }
+        }
+        if full_raise {
+            self.min_raise = to - self.current_bet;
+        }
+        self.current_bet = to;
+        Ok(())
+    }
+
+    // Passes the turn to the next seat after `from` that still has to act, if any.
+    fn advance(&mut self, from: usize) {
+        let n = self.seats.len();
+        let active = self.seats.iter().filter(|seat| seat.is_active()).count();
+        self.to_act = if self.is_hand_over() {
+            None
+        } else {
+            (1..=n).map(|i| (from + i) % n).find(|&i| {
+                let seat = &self.seats[i];
+                // A lone player who has matched every bet has nobody left to bet against.
+                let settled = active == 1 && seat.street_bet >= self.current_bet;
+                self.needs_to_act[i] && seat.is_active() && !settled
+            })
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(betting: &mut Betting, actions: &[Action]) {
+        for &action in actions {
+            let seat = betting.to_act().expect("someone should be to act");
+            betting.act(seat, action).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!("check".parse(), Ok(Action::Check));
+        assert_eq!(" Raise 60 ".parse(), Ok(Action::Raise(60)));
+        assert_eq!("all in".parse(), Ok(Action::AllIn));
+        assert!("bet".parse::<Action>().is_err());
+        assert!("bet lots".parse::<Action>().is_err());
+        assert!("shove".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn test_blinds_and_first_to_act() {
+        let betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        assert_eq!(betting.seats()[1].street_bet, 1);
+        assert_eq!(betting.seats()[2].street_bet, 2);
+        assert_eq!(betting.to_act(), Some(0));
+        assert_eq!(betting.current_bet(), 2);
+        assert_eq!(betting.pot(), 3);
+    }
+
+    #[test]
+    fn test_bad_hands_are_refused() {
+        let new = |stacks: &[Chips], dealer, small, big| Betting::new(stacks, dealer, small, big);
+        assert_eq!(new(&[100], 0, 1, 2), Err(BettingError::TooFewSeats));
+        assert_eq!(new(&[100, 0], 0, 1, 2), Err(BettingError::EmptyStack));
+        assert_eq!(
+            new(&[100, 100], 2, 1, 2),
+            Err(BettingError::DealerNotSeated)
+        );
+        assert_eq!(new(&[100, 100], 0, 0, 0), Err(BettingError::InvalidBlinds));
+        assert_eq!(new(&[100, 100], 0, 5, 2), Err(BettingError::InvalidBlinds));
+        assert!(new(&[100, 100], 0, 0, 2).is_ok());
+    }
+
+    #[test]
+    fn test_heads_up_positions() {
+        let mut betting = Betting::new(&[100, 100], 0, 1, 2).unwrap();
+        assert_eq!(betting.seats()[0].street_bet, 1);
+        assert_eq!(betting.to_act(), Some(0));
+        play(&mut betting, &[Action::Call, Action::Check]);
+        assert!(betting.is_round_complete());
+
+        betting.next_street().unwrap();
+        assert_eq!(betting.to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_big_blind_gets_option() {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        play(&mut betting, &[Action::Call, Action::Call]);
+        assert_eq!(betting.to_act(), Some(2));
+        play(&mut betting, &[Action::Raise(6)]);
+        assert_eq!(betting.to_act(), Some(0));
+        play(&mut betting, &[Action::Call, Action::Fold]);
+        assert!(betting.is_round_complete());
+        assert_eq!(betting.pot(), 14);
+    }
+
+    #[test]
+    fn test_check_around_completes_street() {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        play(&mut betting, &[Action::Call, Action::Call, Action::Check]);
+        betting.next_street().unwrap();
+        assert_eq!(betting.to_act(), Some(1));
+        play(&mut betting, &[Action::Check, Action::Check, Action::Check]);
+        assert!(betting.is_round_complete());
+        assert_eq!(betting.current_bet(), 0);
+    }
+
+    #[test]
+    fn test_turn_order_is_enforced() {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        assert_eq!(betting.act(1, Action::Call), Err(BettingError::NotYourTurn));
+        play(&mut betting, &[Action::Call, Action::Call, Action::Check]);
+        assert_eq!(
+            betting.act(0, Action::Check),
+            Err(BettingError::RoundComplete)
+        );
+    }
+
+    #[test]
+    fn test_invalid_actions() {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        assert_eq!(
+            betting.act(0, Action::Check),
+            Err(BettingError::CannotCheck)
+        );
+        assert_eq!(
+            betting.act(0, Action::Bet(10)),
+            Err(BettingError::BetAlreadyMade)
+        );
+        assert_eq!(
+            betting.act(0, Action::Raise(3)),
+            Err(BettingError::BelowMinimum { minimum: 4 })
+        );
+        assert_eq!(
+            betting.act(0, Action::Raise(101)),
+            Err(BettingError::InsufficientChips)
+        );
+
+        play(&mut betting, &[Action::Call, Action::Call, Action::Check]);
+        betting.next_street().unwrap();
+        assert_eq!(
+            betting.act(1, Action::Call),
+            Err(BettingError::NothingToCall)
+        );
+        assert_eq!(
+            betting.act(1, Action::Raise(10)),
+            Err(BettingError::NoBetToRaise)
+        );
+        assert_eq!(
+            betting.act(1, Action::Bet(1)),
+            Err(BettingError::BelowMinimum { minimum: 2 })
+        );
+        assert!(betting.act(1, Action::Bet(2)).is_ok());
+    }
+
+    #[test]
+    fn test_min_raise_follows_last_raise() {
+        let mut betting = Betting::new(&[1000, 1000, 1000], 0, 5, 10).unwrap();
+        play(&mut betting, &[Action::Raise(40)]);
+        assert_eq!(betting.min_raise_to(), 70);
+        assert_eq!(
+            betting.act(1, Action::Raise(60)),
+            Err(BettingError::BelowMinimum { minimum: 70 })
+        );
+        play(&mut betting, &[Action::Raise(70)]);
+        assert_eq!(betting.min_raise_to(), 100);
+    }
+
+    #[test]
+    fn test_fold_to_a_bet_ends_hand() {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        play(
+            &mut betting,
+            &[Action::Raise(10), Action::Fold, Action::Fold],
+        );
+        assert!(betting.is_hand_over());
+        assert!(betting.is_round_complete());
+        assert_eq!(betting.pot(), 13);
+    }
+
+    #[test]
+    fn test_all_in_call_for_less() {
+        let mut betting = Betting::new(&[100, 30], 0, 1, 2).unwrap();
+        play(&mut betting, &[Action::Raise(50), Action::Call]);
+        assert!(betting.seats()[1].all_in);
+        assert_eq!(betting.seats()[1].total_bet, 30);
+        assert!(betting.is_round_complete());
+
+        // Nobody is left to bet against on later streets.
+        betting.next_street().unwrap();
+        assert!(betting.is_round_complete());
+    }
+
+    #[test]
+    fn test_short_all_in_does_not_reopen_betting() {
+        let mut betting = Betting::new(&[100, 100, 25], 0, 1, 2).unwrap();
+        // Dealer raises to 20, small blind calls, big blind shoves 25: 5 more is not a full raise.
+        play(
+            &mut betting,
+            &[Action::Raise(20), Action::Call, Action::AllIn],
+        );
+        assert_eq!(betting.current_bet(), 25);
+        assert_eq!(betting.to_act(), Some(0));
+        assert_eq!(
+            betting.act(0, Action::Raise(60)),
+            Err(BettingError::RaiseNotReopened)
+        );
+        play(&mut betting, &[Action::Call, Action::Call]);
+        assert!(betting.is_round_complete());
+        assert_eq!(betting.pot(), 75);
+    }
+
+    #[test]
+    fn test_full_all_in_raise_reopens_betting() {
+        let mut betting = Betting::new(&[100, 100, 50], 0, 1, 2).unwrap();
+        play(
+            &mut betting,
+            &[Action::Raise(20), Action::Call, Action::AllIn],
+        );
+        assert_eq!(betting.min_raise_to(), 80);
+        play(&mut betting, &[Action::Raise(80)]);
+        assert_eq!(betting.to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_all_in_blind() {
+        let betting = Betting::new(&[100, 1], 0, 1, 2).unwrap();
+        // Heads-up the dealer posts the small blind; the big blind only has one chip.
+        assert!(betting.seats()[1].all_in);
+        assert_eq!(betting.to_act(), None);
+        assert_eq!(betting.pot(), 2);
+    }
+
+    #[test]
+    fn test_next_street_requires_complete_round() {
+        let mut betting = Betting::new(&[100, 100], 0, 1, 2).unwrap();
+        assert_eq!(betting.next_street(), Err(BettingError::RoundNotComplete));
+    }
+}
@@ -0,0 +1,367 @@
+use pba5_vrf_poker_game_group2::{
+    check_blinds, generate_seed, Action, Card, Client, Effect, GameId, Keystore, Peer, PeerError,
+    Player, PokerError, Table, TableConfig, Update,
+};
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::str::FromStr;
+
+const USAGE: &str = "usage: client --name NAME [--addr HOST:PORT] [--keystore FILE]
+       client --name NAME --game HEX --listen HOST:PORT [--dial HOST:PORT]... [--keystore FILE]
+              [--seats N] [--stack CHIPS] [--blinds SMALL/BIG] [--hands N]";
+
+// Playing without a host: every peer must be started with the same game and table settings.
+struct PeerArgs {
+    game_id: GameId,
+    listen: String,
+    dial: Vec<SocketAddr>,
+    config: TableConfig,
+}
+
+struct Args {
+    name: String,
+    addr: String,
+    keystore: Option<String>,
+    peer: Option<PeerArgs>,
+}
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("bad value for {}", flag))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut name = None;
+    let mut addr = "127.0.0.1:7878".to_string();
+    let mut keystore = None;
+    let mut game_id = None;
+    let mut listen = None;
+    let mut dial = Vec::new();
+    let mut config = TableConfig {
+        seats: 2,
+        stack: 1000,
+        small_blind: 5,
+        big_blind: 10,
+        max_hands: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--name" => name = Some(value),
+            "--addr" => addr = value,
+            "--keystore" => keystore = Some(value),
+            "--game" => {
+                let mut id = GameId::default();
+                hex::decode_to_slice(&value, &mut id)
+                    .map_err(|_| format!("bad value for {}", flag))?;
+                game_id = Some(id);
+            }
+            "--listen" => listen = Some(value),
+            "--dial" => dial.push(number(&flag, &value)?),
+            "--seats" => config.seats = number(&flag, &value)?,
+            "--stack" => config.stack = number(&flag, &value)?,
+            "--hands" => config.max_hands = Some(number(&flag, &value)?),
+            "--blinds" => {
+                let (small, big) = value
+                    .split_once('/')
+                    .and_then(|(small, big)| Some((small.parse().ok()?, big.parse().ok()?)))
+                    .ok_or_else(|| format!("bad value for {}", flag))?;
+                check_blinds(small, big)
+                    .map_err(|err| format!("bad value for {}: {}", flag, err))?;
+                config.small_blind = small;
+                config.big_blind = big;
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    let name = name.ok_or("--name is required")?;
+    let peer = match (game_id, listen) {
+        (Some(game_id), Some(listen)) => {
+            if config.seats < 2 || dial.len() >= config.seats {
+                return Err(
+                    "a table needs at least 2 seats, and fewer --dial peers than seats".to_string(),
+                );
+            }
+            Some(PeerArgs {
+                game_id,
+                listen,
+                dial,
+                config,
+            })
+        }
+        (None, None) => None,
+        _ => return Err("--game and --listen go together".to_string()),
+    };
+    Ok(Args {
+        name,
+        addr,
+        keystore,
+        peer,
+    })
+}
+
+fn read_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+// Our identity never leaves this machine: it is either throwaway or kept in a keystore.
+fn load_player(keystore: Option<&str>) -> Result<Player, PokerError> {
+    let Some(path) = keystore else {
+        return Ok(Player::new());
+    };
+    let passphrase = read_line("Keystore passphrase: ").unwrap_or_default();
+    if Path::new(path).exists() {
+        return Ok(Player::from_keystore(&Keystore::load(path)?, &passphrase)?);
+    }
+    let player = Player::from_seed(&generate_seed());
+    player.export_keystore(&passphrase)?.save(path)?;
+    println!("Created a new identity in {}", path);
+    Ok(player)
+}
+
+fn show(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn name(table: &Table, seat: usize) -> &str {
+    &table.state().players()[seat].0
+}
+
+// Asks until we get an action that is legal right now, so the host never has to refuse one.
+fn prompt_action(table: &Table, seat: usize) -> Action {
+    let betting = table.state().betting().expect("asked to act during a hand");
+    let stack = betting.seats()[seat].stack;
+    println!(
+        "Pot {}, {} to call, minimum raise to {}, you have {}",
+        betting.pot(),
+        betting.amount_to_call(seat),
+        betting.min_raise_to(),
+        stack
+    );
+    loop {
+        let Some(line) = read_line("Your action (fold, check, call, bet N, raise N, allin): ")
+        else {
+            return Action::Fold;
+        };
+        let action: Action = match line.parse() {
+            Ok(action) => action,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+        match betting.clone().act(seat, action) {
+            Ok(()) => return action,
+            Err(err) => println!("Not allowed: {}", err),
+        }
+    }
+}
+
+fn print_effects(table: &Table, us: usize, hole_cards: Option<&[Card]>, effects: &[Effect]) {
+    for effect in effects {
+        match effect {
+            Effect::HandStarted { number, dealer } => {
+                println!("\n=== Hand {} ({} deals) ===", number, name(table, *dealer))
+            }
+            Effect::Shuffled { seat } if *seat != us => {
+                println!("Verified {}'s shuffle", name(table, *seat))
+            }
+            Effect::SharesAccepted { seat } if *seat != us => {
+                println!("Verified {}'s decryption shares", name(table, *seat))
+            }
+            Effect::HoleCardsDealt => {
+                if let Some(cards) = hole_cards {
+                    println!("Your cards: {}", show(cards))
+                }
+            }
+            Effect::Shuffled { .. } | Effect::SharesAccepted { .. } | Effect::ToAct { .. } => {}
+            Effect::HoleCards { seat, cards } => {
+                println!("{} shows {}", name(table, *seat), show(cards))
+            }
+            Effect::Acted { seat, action } => println!("{}: {:?}", name(table, *seat), action),
+            Effect::StreetDealt { street, board } => println!("{:?}: {}", street, show(board)),
+            Effect::HandFinished { settlement, stacks } => {
+                for shown in &settlement.showdown.hands {
+                    println!(
+                        "{} shows {} ({})",
+                        shown.player,
+                        show(&shown.cards),
+                        shown.rank.category
+                    );
+                }
+                for (seat, &won) in settlement.payouts.iter().enumerate() {
+                    if won > 0 {
+                        println!("{} wins {}", name(table, seat), won);
+                    }
+                }
+                for (seat, stack) in stacks.iter().enumerate() {
+                    println!("{} has {} chips", name(table, seat), stack);
+                }
+            }
+            Effect::Forfeited { seats, stacks } => {
+                for &seat in seats {
+                    println!(
+                        "{} timed out or left and forfeited; {} chips left",
+                        name(table, seat),
+                        stacks[seat]
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn warn(problem: &str, blame: &str) {
+    let bar = "!".repeat(72);
+    eprintln!("\n{}", bar);
+    eprintln!("!! VERIFICATION FAILED: {}", problem);
+    eprintln!("!! {} Leaving the table.", blame);
+    eprintln!("{}\n", bar);
+}
+
+fn cheated(who: &str, error: &PokerError) {
+    warn(
+        &format!(
+            "{} sent something that does not check out:\n!!   {}",
+            who, error
+        ),
+        "Either that player is cheating or the host is.",
+    );
+}
+
+fn seated(table: &Table) {
+    let names: Vec<&str> = (0..table.config().seats)
+        .map(|seat| name(table, seat))
+        .collect();
+    println!("Seated: {}", names.join(", "));
+}
+
+fn play(args: &Args) -> Result<(), PokerError> {
+    let player = load_player(args.keystore.as_deref())?;
+    let mut client = Client::join(args.addr.as_str(), &args.name, player)?;
+    println!(
+        "Joined table {} of game {}, waiting for {} players",
+        client.table_id(),
+        hex::encode(client.game_id()),
+        client.config().seats
+    );
+
+    loop {
+        client.respond(prompt_action)?;
+        let update = client.poll()?;
+        let (Some(table), Some(us)) = (client.table(), client.seat()) else {
+            if let Update::Invalid { error, .. } = &update {
+                cheated("the host", error);
+                std::process::exit(1);
+            }
+            continue;
+        };
+        match update {
+            Update::Started => seated(table),
+            Update::Accepted { effects, .. } | Update::TimedOut { effects, .. } => {
+                print_effects(table, us, client.hole_cards(), &effects)
+            }
+            Update::Invalid { seat, error } => {
+                cheated(seat.map_or("the host", |seat| name(table, seat)), &error);
+                std::process::exit(1);
+            }
+            Update::Rejected { reason } => eprintln!("The host refused our message: {}", reason),
+            Update::Finished { stacks } => {
+                println!("\nTable closed. Final stacks: {:?}", stacks);
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Plays with no host: we check every other peer's messages ourselves and compare our
+// table with theirs after each hand.
+fn play_peer(args: &Args, peer_args: &PeerArgs) -> Result<(), Box<dyn Error>> {
+    let player = load_player(args.keystore.as_deref())?;
+    let listener = TcpListener::bind(&peer_args.listen)?;
+    println!(
+        "Waiting for {} peers on {} for game {}",
+        peer_args.config.seats - 1,
+        peer_args.listen,
+        hex::encode(peer_args.game_id)
+    );
+    let mut peer = Peer::connect(
+        peer_args.config,
+        peer_args.game_id,
+        0,
+        &args.name,
+        player,
+        &listener,
+        &peer_args.dial,
+    )?;
+    seated(peer.table());
+
+    loop {
+        let update = match peer.poll(prompt_action) {
+            Ok(update) => update,
+            Err(PeerError::Invalid { seat, error }) => {
+                cheated(name(peer.table(), seat), &error);
+                std::process::exit(1);
+            }
+            Err(PeerError::Diverged { seat, hand }) => {
+                warn(
+                    &format!(
+                        "{} ended hand {} with a different table from ours.",
+                        name(peer.table(), seat),
+                        hand
+                    ),
+                    "They are cheating or playing with different settings.",
+                );
+                std::process::exit(1);
+            }
+            Err(PeerError::TimedOut { seats }) => {
+                let names: Vec<&str> = seats.iter().map(|&seat| name(peer.table(), seat)).collect();
+                eprintln!(
+                    "Gave up waiting on {}; leaving the table.",
+                    names.join(", ")
+                );
+                std::process::exit(1);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        match update {
+            Update::Accepted { effects, .. } => {
+                print_effects(peer.table(), peer.seat(), peer.hole_cards(), &effects)
+            }
+            Update::Finished { stacks } => {
+                println!("\nTable closed. Final stacks: {:?}", stacks);
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+    let played = match &args.peer {
+        Some(peer_args) => play_peer(&args, peer_args),
+        None => play(&args).map_err(Into::into),
+    };
+    if let Err(err) = played {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
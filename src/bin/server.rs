@@ -0,0 +1,90 @@
+use pba5_vrf_poker_game_group2::{check_blinds, random_game_id, Lobby, TableConfig, TableHost};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::thread;
+
+const USAGE: &str =
+    "usage: server [--addr HOST:PORT] [--seats N] [--stack CHIPS] [--blinds SMALL/BIG] [--hands N]";
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("bad value for {}", flag))
+}
+
+fn parse_args() -> Result<(String, TableConfig), String> {
+    let mut addr = "127.0.0.1:7878".to_string();
+    let mut config = TableConfig {
+        seats: 2,
+        stack: 1000,
+        small_blind: 5,
+        big_blind: 10,
+        max_hands: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--addr" => addr = value,
+            "--seats" => config.seats = number(&flag, &value)?,
+            "--stack" => config.stack = number(&flag, &value)?,
+            "--hands" => config.max_hands = Some(number(&flag, &value)?),
+            "--blinds" => {
+                let (small, big) = value
+                    .split_once('/')
+                    .and_then(|(small, big)| Some((small.parse().ok()?, big.parse().ok()?)))
+                    .ok_or_else(|| format!("bad value for {}", flag))?;
+                check_blinds(small, big)
+                    .map_err(|err| format!("bad value for {}: {}", flag, err))?;
+                config.small_blind = small;
+                config.big_blind = big;
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    if config.seats < 2 {
+        return Err("a table needs at least 2 seats".to_string());
+    }
+    Ok((addr, config))
+}
+
+// Fills one table at a time with the players who connect, then hands it to its own thread.
+fn main() {
+    let (addr, config) = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+    let listener = TcpListener::bind(&addr).unwrap_or_else(|err| {
+        eprintln!("cannot listen on {}: {}", addr, err);
+        std::process::exit(1);
+    });
+    println!("Hosting {}-seat tables on {}", config.seats, addr);
+
+    let mut host = TableHost::new(config, random_game_id(), 0);
+    let lobby = Lobby::open(listener, host.welcome());
+    for table_id in 0.. {
+        let filled = lobby.fill(&mut host, |peer, result| match result {
+            Ok(()) => println!("Table {}: seated {}", table_id, peer),
+            Err(err) => eprintln!("Table {}: turned away {}: {}", table_id, peer, err),
+        });
+        if let Err(err) = filled {
+            eprintln!("stopped accepting players: {}", err);
+            std::process::exit(1);
+        }
+        let game_id = *host.game_id();
+        println!("Table {} ({}) is full", table_id, hex::encode(game_id));
+        let full = std::mem::replace(
+            &mut host,
+            TableHost::new(config, random_game_id(), table_id + 1),
+        );
+        thread::spawn(move || match full.run() {
+            Ok(table) => println!(
+                "Table {} finished after {} hands: {:?}",
+                table_id,
+                table.state().hand_number(),
+                table.state().stacks()
+            ),
+            Err(err) => eprintln!("Table {} stopped: {}", table_id, err),
+        });
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    pub fn to_char(self) -> char {
+        match self {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_lowercase() {
+            'c' => Some(Suit::Clubs),
+            'd' => Some(Suit::Diamonds),
+            'h' => Some(Suit::Hearts),
+            's' => Some(Suit::Spades),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Rank {
+    Two = 2,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    // Numeric value from 2 (deuce) to 14 (ace).
+    pub fn value(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_value(value: u8) -> Option<Rank> {
+        Rank::ALL.get(value.checked_sub(2)? as usize).copied()
+    }
+
+    pub fn to_char(self) -> char {
+        b"23456789TJQKA"[self.value() as usize - 2] as char
+    }
+
+    pub fn from_char(c: char) -> Option<Rank> {
+        let c = c.to_ascii_uppercase();
+        Rank::ALL.into_iter().find(|rank| rank.to_char() == c)
+    }
+}
+
+// A playing card. Cards order by rank first, with the suit only breaking ties so the
+// ordering stays consistent with equality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Card {
+    pub rank: Rank,
+    pub suit: Suit,
+}
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Card { rank, suit }
+    }
+
+    // Position of the card in a fresh deck: suits in order, each from deuce to ace.
+    pub fn index(self) -> u8 {
+        self.suit as u8 * 13 + self.rank.value() - 2
+    }
+
+    pub fn from_index(index: u8) -> Option<Card> {
+        let suit = *Suit::ALL.get(index as usize / 13)?;
+        let rank = Rank::from_value(index % 13 + 2)?;
+        Some(Card { rank, suit })
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.rank.to_char(), self.suit.to_char())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardError(String);
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid card {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(rank), Some(suit), None) => Rank::from_char(rank)
+                .zip(Suit::from_char(suit))
+                .map(|(rank, suit)| Card { rank, suit })
+                .ok_or_else(|| ParseCardError(s.to_string())),
+            _ => Err(ParseCardError(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for Card {
+    type Error = ParseCardError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Card> for String {
+    fn from(card: Card) -> Self {
+        card.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let card: Card = "As".parse().unwrap();
+        assert_eq!(card, Card::new(Rank::Ace, Suit::Spades));
+        assert_eq!(card.to_string(), "As");
+
+        let card: Card = "td".parse().unwrap();
+        assert_eq!(card, Card::new(Rank::Ten, Suit::Diamonds));
+        assert_eq!(card.to_string(), "Td");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in ["", "A", "Ax", "1s", "10s", "Ass"] {
+            assert!(s.parse::<Card>().is_err(), "{:?} should not parse", s);
+        }
+    }
+
+    #[test]
+    fn test_index_round_trip() {
+        for index in 0..52 {
+            let card = Card::from_index(index).unwrap();
+            assert_eq!(card.index(), index);
+        }
+        assert_eq!(Card::from_index(0), Some(Card::new(Rank::Two, Suit::Clubs)));
+        assert_eq!(
+            Card::from_index(51),
+            Some(Card::new(Rank::Ace, Suit::Spades))
+        );
+        assert_eq!(Card::from_index(52), None);
+    }
+
+    #[test]
+    fn test_ordering_by_rank() {
+        let ace_of_clubs: Card = "Ac".parse().unwrap();
+        let king_of_spades: Card = "Ks".parse().unwrap();
+        let ace_of_spades: Card = "As".parse().unwrap();
+        assert!(ace_of_clubs > king_of_spades);
+        assert!(ace_of_spades > ace_of_clubs);
+        assert_eq!(ace_of_clubs.rank, ace_of_spades.rank);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let card: Card = "Qh".parse().unwrap();
+        let json = serde_json::to_string(&card).unwrap();
+        assert_eq!(json, "\"Qh\"");
+        assert_eq!(serde_json::from_str::<Card>(&json).unwrap(), card);
+        assert!(serde_json::from_str::<Card>("\"Zz\"").is_err());
+    }
+}
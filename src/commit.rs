@@ -0,0 +1,365 @@
+use crate::betting::Chips;
+use merlin::Transcript;
+use schnorrkel::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const COMMIT_LABEL: &[u8] = b"vrf-poker-commit";
+pub const ROUND_INPUT_LABEL: &[u8] = b"vrf-poker-round-input";
+
+pub type Contribution = [u8; 32];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Commitment(#[serde(with = "crate::wire::hex_encoded")] pub [u8; 32]);
+
+// Hash commitment to a player's random contribution. The player's public key is bound in
+// so nobody can copy another player's commitment and reveal.
+pub fn commit(public: &PublicKey, contribution: &Contribution) -> Commitment {
+    let mut transcript = Transcript::new(COMMIT_LABEL);
+    transcript.append_message(b"public", public.as_ref());
+    transcript.append_message(b"contribution", contribution);
+    let mut commitment = [0u8; 32];
+    transcript.challenge_bytes(b"commitment", &mut commitment);
+    Commitment(commitment)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitError {
+    UnknownParticipant,
+    // The same key is listed for more than one seat.
+    DuplicateParticipant,
+    AlreadyCommitted,
+    // Reveals are only accepted once every participant has committed.
+    CommitPhaseOpen,
+    AlreadyRevealed,
+    CommitmentMismatch,
+    // Seats that committed but have not revealed a matching contribution.
+    MissingReveals(Vec<usize>),
+}
+
+impl fmt::Display for CommitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitError::UnknownParticipant => write!(f, "not a participant in this round"),
+            CommitError::DuplicateParticipant => write!(f, "participant listed twice"),
+            CommitError::AlreadyCommitted => write!(f, "already committed"),
+            CommitError::CommitPhaseOpen => write!(f, "not every participant has committed"),
+            CommitError::AlreadyRevealed => write!(f, "already revealed"),
+            CommitError::CommitmentMismatch => write!(f, "reveal does not match the commitment"),
+            CommitError::MissingReveals(seats) => write!(f, "seats {:?} did not reveal", seats),
+        }
+    }
+}
+
+impl std::error::Error for CommitError {}
+
+// Commit-reveal among the players of a round. Everyone commits to a random contribution,
+// then everyone reveals, and the round input is derived from all reveals. As long as one
+// player picked their contribution honestly, nobody could have chosen the round input.
+#[derive(Debug, Clone)]
+pub struct CommitReveal {
+    participants: Vec<PublicKey>,
+    commitments: Vec<Option<Commitment>>,
+    reveals: Vec<Option<Contribution>>,
+}
+
+impl CommitReveal {
+    pub fn new(participants: Vec<PublicKey>) -> Result<Self, CommitError> {
+        for (seat, public) in participants.iter().enumerate() {
+            if participants[..seat].contains(public) {
+                return Err(CommitError::DuplicateParticipant);
+            }
+        }
+        let n = participants.len();
+        Ok(CommitReveal {
+            participants,
+            commitments: vec![None; n],
+            reveals: vec![None; n],
+        })
+    }
+
+    // Forgets every commitment and reveal so the same participants can start over.
+    pub fn reset(&mut self) {
+        let n = self.participants.len();
+        self.commitments = vec![None; n];
+        self.reveals = vec![None; n];
+    }
+
+    pub fn participants(&self) -> &[PublicKey] {
+        &self.participants
+    }
+
+    fn seat(&self, public: &PublicKey) -> Result<usize, CommitError> {
+        self.participants
+            .iter()
+            .position(|participant| participant == public)
+            .ok_or(CommitError::UnknownParticipant)
+    }
+
+    pub fn commit(
+        &mut self,
+        public: &PublicKey,
+        commitment: Commitment,
+    ) -> Result<(), CommitError> {
+        let seat = self.seat(public)?;
+        if self.commitments[seat].is_some() {
+            return Err(CommitError::AlreadyCommitted);
+        }
+        self.commitments[seat] = Some(commitment);
+        Ok(())
+    }
+
+    pub fn all_committed(&self) -> bool {
+        self.commitments.iter().all(Option::is_some)
+    }
+
+    pub fn reveal(
+        &mut self,
+        public: &PublicKey,
+        contribution: Contribution,
+    ) -> Result<(), CommitError> {
+        let seat = self.seat(public)?;
+        if !self.all_committed() {
+            return Err(CommitError::CommitPhaseOpen);
+        }
+        if self.reveals[seat].is_some() {
+            return Err(CommitError::AlreadyRevealed);
+        }
+        if self.commitments[seat] != Some(commit(public, &contribution)) {
+            return Err(CommitError::CommitmentMismatch);
+        }
+        self.reveals[seat] = Some(contribution);
+        Ok(())
+    }
+
+    pub fn missing_commitments(&self) -> Vec<usize> {
+        (0..self.participants.len())
+            .filter(|&seat| self.commitments[seat].is_none())
+            .collect()
+    }
+
+    // Seats that have not yet revealed a contribution matching their commitment.
+    pub fn missing_reveals(&self) -> Vec<usize> {
+        (0..self.participants.len())
+            .filter(|&seat| self.reveals[seat].is_none())
+            .collect()
+    }
+
+    // Derives the round input once every participant has revealed. Contributions are
+    // absorbed in public-key order so the result does not depend on seating.
+    pub fn finish(&self) -> Result<[u8; 32], CommitError> {
+        if !self.all_committed() {
+            return Err(CommitError::CommitPhaseOpen);
+        }
+        let missing = self.missing_reveals();
+        if !missing.is_empty() {
+            return Err(CommitError::MissingReveals(missing));
+        }
+
+        let mut reveals: Vec<(&PublicKey, &Contribution)> = self
+            .participants
+            .iter()
+            .zip(self.reveals.iter().flatten())
+            .collect();
+        reveals.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+
+        let mut transcript = Transcript::new(ROUND_INPUT_LABEL);
+        for (public, contribution) in reveals {
+            transcript.append_message(b"public", public.as_ref());
+            transcript.append_message(b"contribution", contribution);
+        }
+        let mut input = [0u8; 32];
+        transcript.challenge_bytes(b"round-input", &mut input);
+        Ok(input)
+    }
+}
+
+// Takes up to `penalty` chips from every seat that refused to reveal and shares them
+// among the other seats, odd chips going to the lowest seats. Returns the chips taken.
+pub fn forfeit(stacks: &mut [Chips], offenders: &[usize], penalty: Chips) -> Chips {
+    let honest: Vec<usize> = (0..stacks.len())
+        .filter(|seat| !offenders.contains(seat))
+        .collect();
+    if honest.is_empty() {
+        return 0;
+    }
+
+    let mut collected = 0;
+    for &seat in offenders {
+        let fine = penalty.min(stacks[seat]);
+        stacks[seat] -= fine;
+        collected += fine;
+    }
+
+    let share = collected / honest.len() as Chips;
+    let odd_chips = (collected % honest.len() as Chips) as usize;
+    for (position, &seat) in honest.iter().enumerate() {
+        stacks[seat] += share + if position < odd_chips { 1 } else { 0 };
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    fn players(n: usize) -> Vec<Player> {
+        (0..n).map(|_| Player::new()).collect()
+    }
+
+    fn session(players: &[Player]) -> CommitReveal {
+        CommitReveal::new(players.iter().map(Player::public_key).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_commit_reveal_round() {
+        let mut players = players(3);
+        let mut round = session(&players);
+        for player in players.iter_mut() {
+            let commitment = player.commit_contribution();
+            round.commit(&player.public_key(), commitment).unwrap();
+        }
+        for player in &players {
+            round
+                .reveal(&player.public_key(), player.contribution().unwrap())
+                .unwrap();
+        }
+        let input = round.finish().unwrap();
+        assert_eq!(input, round.finish().unwrap());
+    }
+
+    #[test]
+    fn test_input_depends_on_every_contribution() {
+        let players = players(2);
+        let inputs: Vec<[u8; 32]> = [[1u8; 32], [2u8; 32]]
+            .iter()
+            .map(|second| {
+                let mut round = session(&players);
+                let contributions = [[0u8; 32], *second];
+                for (player, contribution) in players.iter().zip(&contributions) {
+                    round
+                        .commit(
+                            &player.public_key(),
+                            commit(&player.public_key(), contribution),
+                        )
+                        .unwrap();
+                }
+                for (player, contribution) in players.iter().zip(&contributions) {
+                    round.reveal(&player.public_key(), *contribution).unwrap();
+                }
+                round.finish().unwrap()
+            })
+            .collect();
+        assert_ne!(inputs[0], inputs[1]);
+    }
+
+    #[test]
+    fn test_input_ignores_seating_order() {
+        let players = players(2);
+        let contributions = [[3u8; 32], [4u8; 32]];
+        let mut forward = session(&players);
+        let mut backward =
+            CommitReveal::new(players.iter().rev().map(Player::public_key).collect()).unwrap();
+        for (player, contribution) in players.iter().zip(&contributions) {
+            let commitment = commit(&player.public_key(), contribution);
+            forward.commit(&player.public_key(), commitment).unwrap();
+            backward.commit(&player.public_key(), commitment).unwrap();
+        }
+        for (player, contribution) in players.iter().zip(&contributions) {
+            forward.reveal(&player.public_key(), *contribution).unwrap();
+            backward
+                .reveal(&player.public_key(), *contribution)
+                .unwrap();
+        }
+        assert_eq!(forward.finish(), backward.finish());
+    }
+
+    #[test]
+    fn test_reveal_before_all_commit() {
+        let mut players = players(2);
+        let mut round = session(&players);
+        let commitment = players[0].commit_contribution();
+        round.commit(&players[0].public_key(), commitment).unwrap();
+        assert_eq!(
+            round.reveal(&players[0].public_key(), players[0].contribution().unwrap()),
+            Err(CommitError::CommitPhaseOpen)
+        );
+        assert_eq!(round.finish(), Err(CommitError::CommitPhaseOpen));
+    }
+
+    #[test]
+    fn test_bad_reveals_are_rejected() {
+        let mut players = players(2);
+        let outsider = Player::new();
+        let mut round = session(&players);
+        for player in players.iter_mut() {
+            let commitment = player.commit_contribution();
+            round.commit(&player.public_key(), commitment).unwrap();
+        }
+        assert_eq!(
+            round.commit(&players[0].public_key(), Commitment([0; 32])),
+            Err(CommitError::AlreadyCommitted)
+        );
+        assert_eq!(
+            round.reveal(&outsider.public_key(), [0; 32]),
+            Err(CommitError::UnknownParticipant)
+        );
+        assert_eq!(
+            round.reveal(&players[0].public_key(), [0; 32]),
+            Err(CommitError::CommitmentMismatch)
+        );
+        // Replaying someone else's reveal does not match your own commitment.
+        assert_eq!(
+            round.reveal(&players[0].public_key(), players[1].contribution().unwrap()),
+            Err(CommitError::CommitmentMismatch)
+        );
+        round
+            .reveal(&players[0].public_key(), players[0].contribution().unwrap())
+            .unwrap();
+        assert_eq!(
+            round.reveal(&players[0].public_key(), players[0].contribution().unwrap()),
+            Err(CommitError::AlreadyRevealed)
+        );
+    }
+
+    #[test]
+    fn test_missing_reveals_are_detected() {
+        let mut players = players(3);
+        let mut round = session(&players);
+        for player in players.iter_mut() {
+            let commitment = player.commit_contribution();
+            round.commit(&player.public_key(), commitment).unwrap();
+        }
+        round
+            .reveal(&players[1].public_key(), players[1].contribution().unwrap())
+            .unwrap();
+        assert_eq!(round.missing_reveals(), vec![0, 2]);
+        assert_eq!(round.finish(), Err(CommitError::MissingReveals(vec![0, 2])));
+    }
+
+    #[test]
+    fn test_duplicate_participants_are_rejected() {
+        let players = players(2);
+        let publics = vec![
+            players[0].public_key(),
+            players[1].public_key(),
+            players[0].public_key(),
+        ];
+        assert_eq!(
+            CommitReveal::new(publics).err(),
+            Some(CommitError::DuplicateParticipant)
+        );
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let mut stacks = vec![100, 5, 100, 100];
+        assert_eq!(forfeit(&mut stacks, &[0, 1], 20), 25);
+        assert_eq!(stacks, vec![80, 0, 113, 112]);
+
+        let mut stacks = vec![10, 10];
+        assert_eq!(forfeit(&mut stacks, &[0, 1], 20), 0);
+        assert_eq!(stacks, vec![10, 10]);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::card::Card;
+use rand::RngCore;
+use schnorrkel::vrf::VRFInOut;
+
+pub const DECK_SIZE: usize = 52;
+
+// Domain label for expanding a VRF output into the byte stream used to pick cards.
+pub const CARD_LABEL: &[u8] = b"vrf-poker-card";
+
+// Picks an index in 0..n from the VRF output without modulo bias. The output is expanded
+// into a deterministic byte stream and 32-bit values are rejected until one falls below
+// the largest multiple of n, so every index is equally likely and anyone holding the
+// verified output gets the same index.
+pub fn sample_index(output: &VRFInOut, n: usize) -> usize {
+    let mut rng = output.make_merlin_rng(CARD_LABEL);
+    uniform_index(&mut rng, n)
+}
+
+pub(crate) fn uniform_index<R: RngCore>(rng: &mut R, n: usize) -> usize {
+    assert!(
+        n > 0 && n <= u32::MAX as usize,
+        "cannot sample from {} values",
+        n
+    );
+    let n = n as u64;
+    let limit = (1u64 << 32) / n * n;
+    loop {
+        let value = rng.next_u32() as u64;
+        if value < limit {
+            return (value % n) as usize;
+        }
+    }
+}
+
+// The cards that have not been dealt yet. Each VRF output picks one of the remaining
+// cards and removes it, so a hand is always drawn without replacement. Removal keeps
+// the order of the remaining cards, which lets anyone replay the draws from the
+// verified VRF outputs and arrive at the same cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    remaining: Vec<Card>,
+}
+
+impl Deck {
+    pub fn new() -> Self {
+        Deck {
+            remaining: (0..DECK_SIZE as u8).filter_map(Card::from_index).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.remaining.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.remaining.contains(&card)
+    }
+
+    pub fn remaining(&self) -> &[Card] {
+        &self.remaining
+    }
+
+    pub fn draw(&mut self, output: &VRFInOut) -> Option<Card> {
+        self.draw_with(&mut output.make_merlin_rng(CARD_LABEL))
+    }
+
+    // Fisher-Yates shuffle of the remaining cards, e.g. from a joint seed.
+    pub fn shuffle_with<R: RngCore>(&mut self, rng: &mut R) {
+        for i in (1..self.remaining.len()).rev() {
+            let j = uniform_index(rng, i + 1);
+            self.remaining.swap(i, j);
+        }
+    }
+
+    // Draws using any deterministic byte stream, e.g. randomness shared by the table.
+    pub fn draw_with<R: RngCore>(&mut self, rng: &mut R) -> Option<Card> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+        let index = uniform_index(rng, self.remaining.len());
+        Some(self.remaining.remove(index))
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::{CardSlot, DrawContext};
+    use rand::rngs::OsRng;
+    use rand_chacha::rand_core::impls;
+    use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey};
+
+    fn context(round: u32, input: &[u8]) -> DrawContext {
+        DrawContext::new([0; 32], 0, round, CardSlot::Hole(0), input)
+    }
+
+    fn vrf_output(keypair: &Keypair, input: &[u8]) -> VRFInOut {
+        keypair
+            .vrf_sign(context(0, input).transcript(&keypair.public))
+            .0
+    }
+
+    #[test]
+    fn test_new_deck() {
+        let deck = Deck::new();
+        assert_eq!(deck.len(), DECK_SIZE);
+        assert!((0..DECK_SIZE as u8).all(|index| deck.contains(Card::from_index(index).unwrap())));
+    }
+
+    #[test]
+    fn test_draw_whole_deck_without_duplicates() {
+        let keypair = Keypair::generate_with(OsRng);
+        let mut deck = Deck::new();
+        let mut drawn = Vec::new();
+        for i in 0..DECK_SIZE as u32 {
+            let card = deck.draw(&vrf_output(&keypair, &i.to_le_bytes())).unwrap();
+            assert!(!drawn.contains(&card));
+            drawn.push(card);
+        }
+        assert!(deck.is_empty());
+        assert_eq!(deck.draw(&vrf_output(&keypair, b"empty")), None);
+    }
+
+    #[test]
+    fn test_same_output_draws_different_cards() {
+        let keypair = Keypair::generate_with(OsRng);
+        let output = vrf_output(&keypair, b"test");
+        let mut deck = Deck::new();
+        let first = deck.draw(&output).unwrap();
+        let second = deck.draw(&output).unwrap();
+        assert_ne!(first, second);
+        assert!(!deck.contains(first) && !deck.contains(second));
+    }
+
+    #[test]
+    fn test_draws_are_replayable() {
+        let keypair = Keypair::generate_with(OsRng);
+        let outputs: Vec<VRFInOut> = (0..5u8).map(|i| vrf_output(&keypair, &[i])).collect();
+        let mut first = Deck::new();
+        let mut second = Deck::new();
+        for output in &outputs {
+            assert_eq!(first.draw(output), second.draw(output));
+        }
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let mut deck = Deck::new();
+        let mut rng = rand::thread_rng();
+        deck.shuffle_with(&mut rng);
+        assert_ne!(deck, Deck::new());
+        let mut cards = deck.remaining().to_vec();
+        cards.sort_by_key(|card| card.index());
+        assert_eq!(cards, Deck::new().remaining());
+    }
+
+    // Always yields the values it was given, in order.
+    struct FixedRng(Vec<u32>);
+
+    impl RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.remove(0)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            impls::next_u64_via_u32(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_uniform_index_rejects_values_above_limit() {
+        // 2^32 = 82595524 * 52 + 48, so the top 48 values must be rejected.
+        let mut rng = FixedRng(vec![u32::MAX, u32::MAX - 47, u32::MAX - 48, 53]);
+        assert_eq!(uniform_index(&mut rng, 52), ((u32::MAX - 48) % 52) as usize);
+        assert_eq!(uniform_index(&mut rng, 52), 1);
+    }
+
+    #[test]
+    fn test_sample_index_is_deterministic() {
+        let keypair = Keypair::generate_with(OsRng);
+        let output = vrf_output(&keypair, b"test");
+        assert_eq!(sample_index(&output, 52), sample_index(&output, 52));
+        assert_eq!(sample_index(&output, 1), 0);
+    }
+
+    #[test]
+    fn test_card_distribution_is_uniform() {
+        let keypair = MiniSecretKey::from_bytes(&[7; 32])
+            .unwrap()
+            .expand_to_keypair(ExpansionMode::Uniform);
+        let draws_per_card = 100;
+        let mut counts = [0u32; DECK_SIZE];
+        for i in 0..(DECK_SIZE * draws_per_card) as u32 {
+            let output = keypair.vrf_create_hash(context(i, b"").transcript(&keypair.public));
+            counts[Deck::new().draw(&output).unwrap().index() as usize] += 1;
+        }
+
+        // Pearson's chi-squared statistic with 51 degrees of freedom; 87.97 is the
+        // critical value at p = 0.001.
+        let expected = draws_per_card as f64;
+        let chi_squared: f64 = counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        assert!(
+            chi_squared < 87.97,
+            "chi-squared {} for counts {:?}",
+            chi_squared,
+            counts
+        );
+    }
+}
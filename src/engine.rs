@@ -0,0 +1,874 @@
+use crate::betting::{check_blinds, Action, Betting, Chips};
+use crate::card::Card;
+use crate::commit::forfeit;
+use crate::error::PokerError;
+use crate::holdem::{Settlement, Street, MAX_PLAYERS};
+use crate::mental::{
+    hole_card_positions, joint_key, Ciphertext, DeckKey, DecryptionShare, EncryptedDeck,
+    KeyAnnouncement,
+};
+use crate::mental_hand::{MentalHand, Opened};
+use crate::shuffle::ShuffleProof;
+use crate::transcript::{DeckContext, GameId, TableId};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use merlin::Transcript;
+use schnorrkel::PublicKey;
+
+pub const HISTORY_LABEL: &[u8] = b"vrf-poker-history";
+
+// Everything that can happen to a table. Signatures on actions are checked before they
+// get here, e.g. by an `Inbox`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    // Starts the next hand with the round input the players agreed on. Every shuffle and
+    // decryption share of the hand is bound to it.
+    StartHand {
+        input: Vec<u8>,
+    },
+    // `seat`'s pass over the deck, with a proof that it holds the same cards as before.
+    Shuffle {
+        seat: usize,
+        deck: Vec<Ciphertext>,
+        proof: Box<ShuffleProof>,
+    },
+    // `seat`'s decryption shares for the positions in `owed_shares(seat)`, in that order.
+    Shares {
+        seat: usize,
+        shares: Vec<DecryptionShare>,
+    },
+    Act {
+        seat: usize,
+        action: Action,
+    },
+    // Fines `seats` up to `penalty` chips each for holding up the table. A hand they held
+    // up is called off first, and every chip bet in it goes back.
+    Forfeit {
+        seats: Vec<usize>,
+        penalty: Chips,
+    },
+}
+
+// What the rest of the world should learn from an event, in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    HandStarted {
+        number: u32,
+        dealer: usize,
+    },
+    Shuffled {
+        seat: usize,
+    },
+    SharesAccepted {
+        seat: usize,
+    },
+    // Each seat can now open its own hole cards with its deck key, and nobody else's.
+    HoleCardsDealt,
+    // Hole cards shown at showdown.
+    HoleCards {
+        seat: usize,
+        cards: Vec<Card>,
+    },
+    ToAct {
+        seat: usize,
+    },
+    Acted {
+        seat: usize,
+        action: Action,
+    },
+    StreetDealt {
+        street: Street,
+        board: Vec<Card>,
+    },
+    HandFinished {
+        settlement: Settlement,
+        stacks: Vec<Chips>,
+    },
+    Forfeited {
+        seats: Vec<usize>,
+        stacks: Vec<Chips>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Phase {
+    Idle,
+    // Blinds are in; every seat shuffles the deck in turn, in seat order.
+    Shuffling {
+        betting: Betting,
+        deck: Box<EncryptedDeck>,
+        shuffled: usize,
+    },
+    Playing {
+        hand: Box<MentalHand>,
+        betting: Betting,
+    },
+}
+
+// A table of Texas Hold'em as a pure state machine. It never prints, reads the clock or
+// draws randomness: every change comes from an `Event`, so a CLI, a server and a test all
+// drive the same engine and see the same `Effect`s.
+//
+// Hands are dealt from a deck encrypted to every seat's deck key and shuffled by every
+// seat, starting from an order drawn from the hand's round input, so a card only opens
+// once each seat has sent its decryption share. Nobody, the engine included, sees a
+// player's hole cards before that player shows them.
+#[derive(Debug, Clone)]
+pub struct GameState {
+    game_id: GameId,
+    table_id: TableId,
+    players: Vec<(String, PublicKey)>,
+    deck_keys: Vec<PublicKey>,
+    joint_key: RistrettoPoint,
+    stacks: Vec<Chips>,
+    small_blind: Chips,
+    big_blind: Chips,
+    dealer: usize,
+    number: u32,
+    phase: Phase,
+    // Hash chain over every finished hand, for comparing tables without replaying them.
+    history: [u8; 32],
+}
+
+impl GameState {
+    // `deck_keys` holds each seat's announced deck key, in seating order.
+    pub fn new(
+        game_id: GameId,
+        table_id: TableId,
+        players: Vec<(String, PublicKey)>,
+        deck_keys: &[KeyAnnouncement],
+        stacks: Vec<Chips>,
+        blinds: (Chips, Chips),
+    ) -> Result<Self, PokerError> {
+        if players.len() != stacks.len() || players.len() != deck_keys.len() {
+            return Err(PokerError::SeatMismatch);
+        }
+        if players.len() < 2 {
+            return Err(PokerError::TooFewPlayers);
+        }
+        if players.len() > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers);
+        }
+        check_blinds(blinds.0, blinds.1)?;
+        let owners: Vec<PublicKey> = players.iter().map(|(_, public)| *public).collect();
+        let joint_key = joint_key(&owners, deck_keys)?;
+        Ok(GameState {
+            game_id,
+            table_id,
+            players,
+            deck_keys: deck_keys.iter().map(|announced| announced.key).collect(),
+            joint_key,
+            stacks,
+            small_blind: blinds.0,
+            big_blind: blinds.1,
+            dealer: 0,
+            number: 0,
+            phase: Phase::Idle,
+            history: [0; 32],
+        })
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn table_id(&self) -> TableId {
+        self.table_id
+    }
+
+    pub fn players(&self) -> &[(String, PublicKey)] {
+        &self.players
+    }
+
+    pub fn deck_keys(&self) -> &[PublicKey] {
+        &self.deck_keys
+    }
+
+    pub fn joint_key(&self) -> &RistrettoPoint {
+        &self.joint_key
+    }
+
+    // Stacks as of the end of the last hand; chips in the current pot are not included.
+    pub fn stacks(&self) -> &[Chips] {
+        &self.stacks
+    }
+
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+
+    pub fn hand_number(&self) -> u32 {
+        self.number
+    }
+
+    // Two tables that dealt and settled the same hands in the same way have equal histories.
+    pub fn history(&self) -> &[u8; 32] {
+        &self.history
+    }
+
+    pub fn is_idle(&self) -> bool {
+        matches!(self.phase, Phase::Idle)
+    }
+
+    pub fn hand(&self) -> Option<&MentalHand> {
+        match &self.phase {
+            Phase::Playing { hand, .. } => Some(hand),
+            _ => None,
+        }
+    }
+
+    pub fn betting(&self) -> Option<&Betting> {
+        match &self.phase {
+            Phase::Shuffling { betting, .. } | Phase::Playing { betting, .. } => Some(betting),
+            Phase::Idle => None,
+        }
+    }
+
+    pub fn to_act(&self) -> Option<usize> {
+        match &self.phase {
+            Phase::Playing { hand, betting, .. } if !hand.is_opening() => betting.to_act(),
+            _ => None,
+        }
+    }
+
+    // The seat whose shuffle pass comes next.
+    pub fn shuffler(&self) -> Option<usize> {
+        match &self.phase {
+            Phase::Shuffling { shuffled, .. } => Some(*shuffled),
+            _ => None,
+        }
+    }
+
+    // The latest verified deck: the last pass while shuffling, the hand's deck afterwards.
+    pub fn deck(&self) -> Option<&EncryptedDeck> {
+        match &self.phase {
+            Phase::Shuffling { deck, .. } => Some(deck),
+            Phase::Playing { hand, .. } => Some(hand.deck()),
+            Phase::Idle => None,
+        }
+    }
+
+    // The deck positions `seat` has to send decryption shares for right now.
+    pub fn owed_shares(&self, seat: usize) -> Vec<usize> {
+        self.hand().map_or_else(Vec::new, |hand| hand.owed(seat))
+    }
+
+    // Points `key` at the current hand's deck. Every pass of it was verified on the way
+    // in, so the key does not have to check them again.
+    pub fn prepare_key(&self, key: &mut DeckKey) {
+        if let Some(hand) = self.hand() {
+            if key.deck() != Some(hand.deck()) {
+                key.use_verified_deck(hand.deck().clone());
+            }
+        }
+    }
+
+    // Privately opens `seat`'s hole cards with its deck key once `HoleCardsDealt`.
+    pub fn open_hole_cards(&self, seat: usize, key: &mut DeckKey) -> Result<Vec<Card>, PokerError> {
+        let hand = self.hand().ok_or(PokerError::OutOfPhase)?;
+        self.prepare_key(key);
+        hole_card_positions(seat, self.players.len())
+            .into_iter()
+            .map(|position| {
+                key.open_own_card(position, &self.deck_keys, hand.shares(position))
+                    .map_err(PokerError::Mental)
+            })
+            .collect()
+    }
+
+    // Applies one event. On error the state is left exactly as it was.
+    pub fn apply(&mut self, event: Event) -> Result<Vec<Effect>, PokerError> {
+        let mut next = self.clone();
+        let effects = match event {
+            Event::StartHand { input } => next.start_hand(input)?,
+            Event::Shuffle { seat, deck, proof } => next.shuffle(seat, deck, &proof)?,
+            Event::Shares { seat, shares } => next.shares(seat, &shares)?,
+            Event::Act { seat, action } => next.act(seat, action)?,
+            Event::Forfeit { seats, penalty } => next.forfeit(seats, penalty)?,
+        };
+        *self = next;
+        Ok(effects)
+    }
+
+    fn start_hand(&mut self, input: Vec<u8>) -> Result<Vec<Effect>, PokerError> {
+        if !self.is_idle() {
+            return Err(PokerError::OutOfPhase);
+        }
+        if self.stacks.contains(&0) {
+            return Err(PokerError::GameOver);
+        }
+        self.number += 1;
+        self.phase = Phase::Shuffling {
+            betting: Betting::new(&self.stacks, self.dealer, self.small_blind, self.big_blind)?,
+            deck: Box::new(EncryptedDeck::new(
+                DeckContext::new(self.game_id, self.table_id, self.number, &input),
+                self.joint_key,
+            )),
+            shuffled: 0,
+        };
+        Ok(vec![Effect::HandStarted {
+            number: self.number,
+            dealer: self.dealer,
+        }])
+    }
+
+    fn shuffle(
+        &mut self,
+        seat: usize,
+        cards: Vec<Ciphertext>,
+        proof: &ShuffleProof,
+    ) -> Result<Vec<Effect>, PokerError> {
+        let Phase::Shuffling {
+            betting,
+            deck,
+            shuffled,
+        } = &mut self.phase
+        else {
+            return Err(PokerError::OutOfPhase);
+        };
+        if seat != *shuffled {
+            return Err(PokerError::OutOfPhase);
+        }
+        let output = EncryptedDeck::from_cards(deck.context().clone(), self.joint_key, cards);
+        proof.verify(deck, &output)?;
+        **deck = output;
+        *shuffled += 1;
+
+        let mut effects = vec![Effect::Shuffled { seat }];
+        if *shuffled < self.players.len() {
+            return Ok(effects);
+        }
+        let names = self.players.iter().map(|(name, _)| name.clone()).collect();
+        let hand = MentalHand::new(names, self.deck_keys.clone(), (**deck).clone());
+        self.phase = Phase::Playing {
+            hand: Box::new(hand),
+            betting: betting.clone(),
+        };
+        self.advance(&mut effects)?;
+        Ok(effects)
+    }
+
+    fn shares(
+        &mut self,
+        seat: usize,
+        shares: &[DecryptionShare],
+    ) -> Result<Vec<Effect>, PokerError> {
+        let Phase::Playing { hand, betting, .. } = &mut self.phase else {
+            return Err(PokerError::OutOfPhase);
+        };
+        hand.receive(seat, shares)?;
+        let mut effects = vec![Effect::SharesAccepted { seat }];
+        match hand.open()? {
+            None => return Ok(effects),
+            Some(Opened::Hole) => effects.push(Effect::HoleCardsDealt),
+            Some(Opened::Street(street)) => {
+                effects.push(Effect::StreetDealt {
+                    street,
+                    board: hand.board().to_vec(),
+                });
+                betting.next_street()?;
+            }
+            Some(Opened::Showdown(shown)) => effects.extend(
+                shown
+                    .into_iter()
+                    .map(|(seat, cards)| Effect::HoleCards { seat, cards }),
+            ),
+        }
+        self.advance(&mut effects)?;
+        Ok(effects)
+    }
+
+    fn act(&mut self, seat: usize, action: Action) -> Result<Vec<Effect>, PokerError> {
+        if self.to_act().is_none() {
+            return Err(PokerError::OutOfPhase);
+        }
+        let Phase::Playing { betting, .. } = &mut self.phase else {
+            return Err(PokerError::OutOfPhase);
+        };
+        betting.act(seat, action)?;
+        let mut effects = vec![Effect::Acted { seat, action }];
+        self.advance(&mut effects)?;
+        Ok(effects)
+    }
+
+    fn forfeit(&mut self, seats: Vec<usize>, penalty: Chips) -> Result<Vec<Effect>, PokerError> {
+        if seats.is_empty() {
+            return Err(PokerError::OutOfPhase);
+        }
+        if seats.iter().any(|&seat| seat >= self.players.len()) {
+            return Err(PokerError::UnknownPlayer);
+        }
+        // `stacks` only changes when a hand settles, so it is still what it was before.
+        self.phase = Phase::Idle;
+        forfeit(&mut self.stacks, &seats, penalty);
+        Ok(vec![Effect::Forfeited {
+            seats,
+            stacks: self.stacks.clone(),
+        }])
+    }
+
+    // Moves the hand along: asks for somebody to act, or for the shares of the next street
+    // or the showdown once a betting round closes, and settles it when nothing is left.
+    fn advance(&mut self, effects: &mut Vec<Effect>) -> Result<(), PokerError> {
+        let Phase::Playing { hand, betting } = &mut self.phase else {
+            return Ok(());
+        };
+        if hand.is_opening() {
+            return Ok(());
+        }
+        if let Some(seat) = betting.to_act() {
+            effects.push(Effect::ToAct { seat });
+            return Ok(());
+        }
+        if !betting.is_hand_over()
+            && (hand.request_street().is_some() || hand.request_showdown(betting))
+        {
+            return Ok(());
+        }
+
+        let settlement = hand.settle(betting)?;
+        let mut stacks = betting.stacks();
+        for (stack, won) in stacks.iter_mut().zip(&settlement.payouts) {
+            *stack += won;
+        }
+
+        // Only what the whole table has seen: hole cards that were never shown stay out.
+        let mut transcript = Transcript::new(HISTORY_LABEL);
+        transcript.append_message(b"previous", &self.history);
+        transcript.append_u64(b"hand", self.number.into());
+        transcript.append_message(b"input", &hand.deck().context().input);
+        for card in hand.deck().cards() {
+            transcript.append_message(b"deck", card.c1.compress().as_bytes());
+            transcript.append_message(b"deck", card.c2.compress().as_bytes());
+        }
+        for cards in hand.shown().unwrap_or_default().iter().flatten() {
+            for card in cards {
+                transcript.append_message(b"shown", card.to_string().as_bytes());
+            }
+        }
+        for card in hand.board() {
+            transcript.append_message(b"board", card.to_string().as_bytes());
+        }
+        for stack in &stacks {
+            transcript.append_u64(b"stack", *stack);
+        }
+        transcript.challenge_bytes(b"history", &mut self.history);
+
+        self.stacks = stacks.clone();
+        self.dealer = (self.dealer + 1) % self.players.len();
+        self.phase = Phase::Idle;
+        effects.push(Effect::HandFinished { settlement, stacks });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::BettingError;
+    use crate::mental::MentalError;
+    use crate::player::Player;
+    use crate::shuffle::{prove_shuffle, ShuffleError};
+    use rand::rngs::OsRng;
+
+    struct Table {
+        keys: Vec<DeckKey>,
+        state: GameState,
+        // Every event applied so far, with its effects.
+        log: Vec<(Event, Vec<Effect>)>,
+    }
+
+    fn players(n: usize) -> Vec<(String, PublicKey)> {
+        (0..n as u8)
+            .map(|i| {
+                let public = Player::from_seed(&[i; 32]).public_key();
+                (format!("Player {}", i), public)
+            })
+            .collect()
+    }
+
+    fn announce(keys: &[DeckKey], players: &[(String, PublicKey)]) -> Vec<KeyAnnouncement> {
+        keys.iter()
+            .zip(players)
+            .map(|(key, (_, public))| key.announce(public))
+            .collect()
+    }
+
+    fn table(n: usize, stack: Chips) -> Table {
+        let players = players(n);
+        let keys: Vec<DeckKey> = (0..n).map(|_| DeckKey::generate(OsRng)).collect();
+        let announced = announce(&keys, &players);
+        let state =
+            GameState::new([9; 32], 1, players, &announced, vec![stack; n], (5, 10)).unwrap();
+        Table {
+            keys,
+            state,
+            log: Vec::new(),
+        }
+    }
+
+    impl Table {
+        fn apply(&mut self, event: Event) -> Vec<Effect> {
+            let effects = self.state.apply(event.clone()).unwrap();
+            self.log.push((event, effects.clone()));
+            effects
+        }
+
+        fn shares(&mut self, seat: usize) -> Event {
+            self.state.prepare_key(&mut self.keys[seat]);
+            let shares = self
+                .state
+                .owed_shares(seat)
+                .into_iter()
+                .map(|position| self.keys[seat].decryption_share(position).unwrap())
+                .collect();
+            Event::Shares { seat, shares }
+        }
+
+        // Sends every decryption share the table is waiting for; returns the effects.
+        fn open(&mut self) -> Vec<Effect> {
+            let mut effects = Vec::new();
+            while let Some(seat) =
+                (0..self.keys.len()).find(|&seat| !self.state.owed_shares(seat).is_empty())
+            {
+                let event = self.shares(seat);
+                effects.extend(self.apply(event));
+            }
+            effects
+        }
+
+        // Starts a hand, shuffles and opens the hole cards; returns the effects of that.
+        fn deal(&mut self, input: &[u8]) -> Vec<Effect> {
+            self.apply(Event::StartHand {
+                input: input.to_vec(),
+            });
+            while let Some(seat) = self.state.shuffler() {
+                let (deck, proof) = prove_shuffle(self.state.deck().unwrap(), &mut OsRng);
+                self.apply(Event::Shuffle {
+                    seat,
+                    deck: deck.cards().to_vec(),
+                    proof: Box::new(proof),
+                });
+            }
+            self.open()
+        }
+
+        fn check_or_call(&mut self) -> Vec<Effect> {
+            let seat = self.state.to_act().unwrap();
+            let action = if self.state.betting().unwrap().amount_to_call(seat) == 0 {
+                Action::Check
+            } else {
+                Action::Call
+            };
+            let mut effects = self.apply(Event::Act { seat, action });
+            effects.extend(self.open());
+            effects
+        }
+    }
+
+    fn shown(effects: &[Effect]) -> Vec<Card> {
+        effects
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::HoleCards { cards, .. } => Some(cards.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn test_hand_to_showdown() {
+        let mut table = table(3, 100);
+        let effects = table.deal(b"hand");
+        assert!(effects.contains(&Effect::HoleCardsDealt));
+        assert!(shown(&effects).is_empty());
+        assert_eq!(effects.last(), Some(&Effect::ToAct { seat: 0 }));
+
+        let mut dealt = Vec::new();
+        for seat in 0..3 {
+            let cards = table
+                .state
+                .open_hole_cards(seat, &mut table.keys[seat])
+                .unwrap();
+            dealt.extend(cards);
+        }
+
+        let mut all = Vec::new();
+        while !table.state.is_idle() {
+            all.extend(table.check_or_call());
+        }
+        let streets: Vec<Street> = all
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::StreetDealt { street, .. } => Some(*street),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(streets, vec![Street::Flop, Street::Turn, Street::River]);
+        // At showdown everybody shows the cards they opened on their own.
+        assert_eq!(shown(&all), dealt);
+        match all.last() {
+            Some(Effect::HandFinished { settlement, stacks }) => {
+                assert_eq!(settlement.showdown.hands.len(), 3);
+                assert_eq!(stacks.iter().sum::<Chips>(), 300);
+                assert_eq!(table.state.stacks(), &stacks[..]);
+            }
+            other => panic!("hand did not finish: {:?}", other),
+        }
+        assert_eq!(table.state.dealer(), 1);
+    }
+
+    #[test]
+    fn test_hole_cards_stay_private() {
+        let mut table = table(2, 100);
+        table.deal(b"private");
+        let mine = table.state.open_hole_cards(0, &mut table.keys[0]).unwrap();
+        assert_eq!(mine.len(), 2);
+        // Seat 1 never shared its own cards, so seat 0's key cannot open them.
+        assert_eq!(
+            table.state.open_hole_cards(1, &mut table.keys[0]),
+            Err(PokerError::Mental(MentalError::MissingShare(1)))
+        );
+    }
+
+    #[test]
+    fn test_same_events_same_effects() {
+        let mut first = table(2, 100);
+        let mut second = first.state.clone();
+        first.deal(b"same");
+        while !first.state.is_idle() {
+            first.check_or_call();
+        }
+        for (event, effects) in &first.log {
+            assert_eq!(&second.apply(event.clone()).unwrap(), effects);
+        }
+        assert_eq!(first.state.stacks(), second.stacks());
+        assert_eq!(first.state.history(), second.history());
+        assert_ne!(first.state.history(), &[0; 32]);
+    }
+
+    #[test]
+    fn test_fold_ends_hand() {
+        let mut table = table(2, 100);
+        table.deal(b"fold");
+        let seat = table.state.to_act().unwrap();
+        let effects = table.apply(Event::Act {
+            seat,
+            action: Action::Fold,
+        });
+        assert!(shown(&effects).is_empty());
+        match effects.last() {
+            Some(Effect::HandFinished { stacks, .. }) => {
+                assert_eq!(stacks[seat], 95);
+                assert_eq!(stacks[1 - seat], 105);
+            }
+            other => panic!("hand did not finish: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejected_events_leave_state_alone() {
+        let mut table = table(2, 100);
+        assert_eq!(
+            table.state.apply(Event::Act {
+                seat: 0,
+                action: Action::Check
+            }),
+            Err(PokerError::OutOfPhase)
+        );
+        table.apply(Event::StartHand {
+            input: b"reject".to_vec(),
+        });
+
+        // Seat 0 shuffles first, and a pass has to match its proof.
+        let (deck, proof) = prove_shuffle(table.state.deck().unwrap(), &mut OsRng);
+        let pass = |seat, cards: Vec<Ciphertext>| Event::Shuffle {
+            seat,
+            deck: cards,
+            proof: Box::new(proof.clone()),
+        };
+        assert_eq!(
+            table.state.apply(pass(1, deck.cards().to_vec())),
+            Err(PokerError::OutOfPhase)
+        );
+        let mut swapped = deck.cards().to_vec();
+        swapped.swap(0, 1);
+        assert!(matches!(
+            table.state.apply(pass(0, swapped)),
+            Err(PokerError::Shuffle(_))
+        ));
+        table.apply(pass(0, deck.cards().to_vec()));
+        let (deck, proof) = prove_shuffle(table.state.deck().unwrap(), &mut OsRng);
+        table.apply(Event::Shuffle {
+            seat: 1,
+            deck: deck.cards().to_vec(),
+            proof: Box::new(proof),
+        });
+
+        // Shares have to come from the sender's deck key, one for every card it owes.
+        let Event::Shares { shares, .. } = table.shares(0) else {
+            unreachable!()
+        };
+        assert_eq!(
+            table.state.apply(Event::Shares {
+                seat: 1,
+                shares: shares.clone()
+            }),
+            Err(PokerError::Mental(MentalError::InvalidShare(1)))
+        );
+        assert_eq!(
+            table.state.apply(Event::Shares {
+                seat: 0,
+                shares: shares[1..].to_vec()
+            }),
+            Err(PokerError::Mental(MentalError::MissingShare(0)))
+        );
+        table.apply(Event::Shares {
+            seat: 0,
+            shares: shares.clone(),
+        });
+        assert_eq!(
+            table.state.apply(Event::Shares { seat: 0, shares }),
+            Err(PokerError::OutOfPhase)
+        );
+        // Nobody acts before everyone can see their cards.
+        assert_eq!(
+            table.state.apply(Event::Act {
+                seat: 0,
+                action: Action::Call
+            }),
+            Err(PokerError::OutOfPhase)
+        );
+        assert_eq!(
+            table.state.apply(Event::StartHand { input: Vec::new() }),
+            Err(PokerError::OutOfPhase)
+        );
+        assert_eq!(table.state.hand_number(), 1);
+    }
+
+    #[test]
+    fn test_passes_do_not_carry_over_between_hands() {
+        // Seat 0 shuffles the same open deck every hand, so without the hand in the proof
+        // it could send last hand's pass again.
+        let mut table = table(2, 100);
+        table.deal(b"same");
+        let replay = table
+            .log
+            .iter()
+            .find_map(|(event, _)| match event {
+                Event::Shuffle { seat: 0, .. } => Some(event.clone()),
+                _ => None,
+            })
+            .unwrap();
+        let seat = table.state.to_act().unwrap();
+        table.apply(Event::Act {
+            seat,
+            action: Action::Fold,
+        });
+        table.apply(Event::StartHand {
+            input: b"same".to_vec(),
+        });
+        assert_eq!(
+            table.state.apply(replay),
+            Err(PokerError::Shuffle(ShuffleError::InvalidProof))
+        );
+    }
+
+    #[test]
+    fn test_forfeit_calls_off_the_hand() {
+        let mut table = table(2, 100);
+        table.apply(Event::StartHand {
+            input: b"stall".to_vec(),
+        });
+        assert_eq!(table.state.shuffler(), Some(0));
+        let effects = table.apply(Event::Forfeit {
+            seats: vec![0],
+            penalty: 100,
+        });
+        assert_eq!(
+            effects,
+            vec![Effect::Forfeited {
+                seats: vec![0],
+                stacks: vec![0, 200],
+            }]
+        );
+        assert!(table.state.is_idle());
+        assert_eq!(
+            table.state.apply(Event::Forfeit {
+                seats: vec![2],
+                penalty: 100
+            }),
+            Err(PokerError::UnknownPlayer)
+        );
+    }
+
+    #[test]
+    fn test_illegal_action() {
+        let mut table = table(2, 100);
+        table.deal(b"illegal");
+        let seat = table.state.to_act().unwrap();
+        assert!(matches!(
+            table.state.apply(Event::Act {
+                seat: 1 - seat,
+                action: Action::Call
+            }),
+            Err(PokerError::IllegalAction(_))
+        ));
+        assert_eq!(table.state.to_act(), Some(seat));
+    }
+
+    #[test]
+    fn test_bad_seating_is_an_error() {
+        let players = players(2);
+        let keys: Vec<DeckKey> = (0..2).map(|_| DeckKey::generate(OsRng)).collect();
+        let announced = announce(&keys, &players);
+        let new = |players: &[(String, PublicKey)], announced: &[KeyAnnouncement], stacks| {
+            GameState::new([9; 32], 1, players.to_vec(), announced, stacks, (5, 10)).err()
+        };
+        assert_eq!(
+            new(&players, &announced, vec![100]),
+            Some(PokerError::SeatMismatch)
+        );
+        assert_eq!(
+            new(&players, &announced[..1], vec![100, 100]),
+            Some(PokerError::SeatMismatch)
+        );
+        assert_eq!(
+            new(&players[..1], &announced[..1], vec![100]),
+            Some(PokerError::TooFewPlayers)
+        );
+        assert_eq!(new(&[], &[], Vec::new()), Some(PokerError::TooFewPlayers));
+        // A deck key announced for another seat does not count.
+        let swapped = [announced[1].clone(), announced[0].clone()];
+        assert_eq!(
+            new(&players, &swapped, vec![100, 100]),
+            Some(PokerError::Mental(MentalError::InvalidKey(0)))
+        );
+        // Blinds are checked when the table is set up, not when the first hand starts.
+        assert_eq!(
+            GameState::new([9; 32], 1, players, &announced, vec![100, 100], (10, 5)).err(),
+            Some(PokerError::IllegalAction(BettingError::InvalidBlinds))
+        );
+    }
+
+    #[test]
+    fn test_game_over() {
+        let mut table = table(2, 10);
+        table.deal(b"all in");
+        let seat = table.state.to_act().unwrap();
+        table.apply(Event::Act {
+            seat,
+            action: Action::AllIn,
+        });
+        // With both players all in, the board and the showdown open without more betting.
+        let effects = table.open();
+        assert_eq!(shown(&effects).len(), 4);
+        assert!(table.state.is_idle());
+        if table.state.stacks().contains(&0) {
+            assert_eq!(
+                table.state.apply(Event::StartHand { input: Vec::new() }),
+                Err(PokerError::GameOver)
+            );
+        }
+    }
+}
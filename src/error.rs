@@ -0,0 +1,187 @@
+use crate::beacon::BeaconError;
+use crate::betting::BettingError;
+use crate::card::Card;
+use crate::commit::CommitError;
+use crate::inbox::InboxError;
+use crate::keystore::KeystoreError;
+use crate::mental::MentalError;
+use crate::net::NetError;
+use crate::shuffle::ShuffleError;
+use crate::threshold::ThresholdError;
+use crate::verify::InvalidClaims;
+use crate::wire::WireError;
+use std::fmt;
+
+// Every way a poker operation can fail. Modules with their own error type are wrapped so
+// callers can handle everything through one `Result`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PokerError {
+    // The player has not made a VRF draw yet.
+    NotDrawn,
+    // A draw or action from someone who is not seated, or on behalf of another seat.
+    UnknownPlayer,
+    // The event is not allowed at this point of the hand.
+    OutOfPhase,
+    // Somebody has run out of chips, so no further hand can be dealt.
+    GameOver,
+    // Join messages that do not seat the table: too few or many, repeated or not joins.
+    InvalidJoin,
+    // Per-seat state, such as stacks or a betting round, for a different number of seats.
+    SeatMismatch,
+    // A table needs at least two players.
+    TooFewPlayers,
+    // More players than one deck can deal to.
+    TooManyPlayers,
+    InvalidProof,
+    // The draw was made under a different context than the one it is being checked against.
+    InputMismatch,
+    DuplicateCard(Card),
+    WrongCardCount(usize),
+    DeckEmpty,
+    // A verified draw selects a different card than the one that was dealt.
+    CardMismatch,
+    IllegalAction(BettingError),
+    // Positions of the claims that failed a batch verification.
+    InvalidClaims(Vec<usize>),
+    Commit(CommitError),
+    Mental(MentalError),
+    Shuffle(ShuffleError),
+    Threshold(ThresholdError),
+    Wire(WireError),
+    Inbox(InboxError),
+    Keystore(KeystoreError),
+    Beacon(BeaconError),
+    Net(NetError),
+}
+
+impl fmt::Display for PokerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PokerError::NotDrawn => write!(f, "no card has been drawn"),
+            PokerError::UnknownPlayer => write!(f, "not a seated player"),
+            PokerError::OutOfPhase => write!(f, "not allowed at this point of the hand"),
+            PokerError::GameOver => write!(f, "a player has no chips left"),
+            PokerError::InvalidJoin => write!(f, "joins do not seat the table"),
+            PokerError::SeatMismatch => write!(f, "state is for a different number of seats"),
+            PokerError::TooFewPlayers => write!(f, "a table needs at least two players"),
+            PokerError::TooManyPlayers => write!(f, "too many players for one deck"),
+            PokerError::InvalidProof => write!(f, "VRF proof does not verify"),
+            PokerError::InputMismatch => write!(f, "draw was made for a different input"),
+            PokerError::DuplicateCard(card) => write!(f, "card {} appears twice", card),
+            PokerError::WrongCardCount(count) => {
+                write!(f, "expected 5 to 7 cards, got {}", count)
+            }
+            PokerError::DeckEmpty => write!(f, "no cards left in the deck"),
+            PokerError::CardMismatch => write!(f, "dealt card does not match its draw"),
+            PokerError::IllegalAction(err) => write!(f, "illegal action: {}", err),
+            PokerError::InvalidClaims(failed) => {
+                write!(f, "invalid VRF proofs at positions {:?}", failed)
+            }
+            PokerError::Commit(err) => write!(f, "{}", err),
+            PokerError::Mental(err) => write!(f, "{}", err),
+            PokerError::Shuffle(err) => write!(f, "{}", err),
+            PokerError::Threshold(err) => write!(f, "{}", err),
+            PokerError::Wire(err) => write!(f, "{}", err),
+            PokerError::Inbox(err) => write!(f, "{}", err),
+            PokerError::Keystore(err) => write!(f, "{}", err),
+            PokerError::Beacon(err) => write!(f, "{}", err),
+            PokerError::Net(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PokerError {}
+
+impl From<BettingError> for PokerError {
+    fn from(err: BettingError) -> Self {
+        PokerError::IllegalAction(err)
+    }
+}
+
+impl From<InvalidClaims> for PokerError {
+    fn from(InvalidClaims(failed): InvalidClaims) -> Self {
+        PokerError::InvalidClaims(failed)
+    }
+}
+
+impl From<CommitError> for PokerError {
+    fn from(err: CommitError) -> Self {
+        PokerError::Commit(err)
+    }
+}
+
+impl From<MentalError> for PokerError {
+    fn from(err: MentalError) -> Self {
+        PokerError::Mental(err)
+    }
+}
+
+impl From<ShuffleError> for PokerError {
+    fn from(err: ShuffleError) -> Self {
+        PokerError::Shuffle(err)
+    }
+}
+
+impl From<ThresholdError> for PokerError {
+    fn from(err: ThresholdError) -> Self {
+        PokerError::Threshold(err)
+    }
+}
+
+impl From<WireError> for PokerError {
+    fn from(err: WireError) -> Self {
+        PokerError::Wire(err)
+    }
+}
+
+impl From<InboxError> for PokerError {
+    fn from(err: InboxError) -> Self {
+        PokerError::Inbox(err)
+    }
+}
+
+impl From<KeystoreError> for PokerError {
+    fn from(err: KeystoreError) -> Self {
+        PokerError::Keystore(err)
+    }
+}
+
+impl From<BeaconError> for PokerError {
+    fn from(err: BeaconError) -> Self {
+        PokerError::Beacon(err)
+    }
+}
+
+impl From<NetError> for PokerError {
+    fn from(err: NetError) -> Self {
+        PokerError::Net(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::Betting;
+
+    fn bet_out_of_turn() -> Result<(), PokerError> {
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        betting.act(2, crate::betting::Action::Call)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_errors_convert() {
+        assert_eq!(
+            bet_out_of_turn(),
+            Err(PokerError::IllegalAction(BettingError::NotYourTurn))
+        );
+        assert_eq!(
+            PokerError::from(InvalidClaims(vec![2])),
+            PokerError::InvalidClaims(vec![2])
+        );
+        assert_eq!(
+            PokerError::from(WireError::BadSignature).to_string(),
+            WireError::BadSignature.to_string()
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use crate::beacon::{BeaconError, RandomnessBeacon, RoundInput};
+use crate::card::Card;
+use crate::commit::{CommitError, CommitReveal};
+use crate::deck::Deck;
+use crate::error::PokerError;
+use crate::holdem::Holdem;
+use crate::inbox::Inbox;
+use crate::player::Player;
+use crate::transcript::{random_game_id, CardSlot, DrawContext, GameId, TableId};
+use crate::verify::verify_each;
+
+// A single player's result for one round: their card, or why they have none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draw {
+    pub player: String,
+    pub card: Result<Card, PokerError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round {
+    pub number: u32,
+    pub input: Vec<u8>,
+    pub draws: Vec<Draw>,
+    pub winner: Option<String>,
+}
+
+// A table of named players. Players are kept in seating order so rounds are reproducible.
+// Every draw is bound to the game id and table id, so proofs never carry over between games.
+#[derive(Debug)]
+pub struct Game {
+    id: GameId,
+    table: TableId,
+    players: Vec<(String, Player)>,
+    round: u32,
+    // Where round inputs come from; commit-reveal among the players if unset.
+    beacon: Option<Box<dyn RandomnessBeacon>>,
+}
+
+impl Game {
+    // A game with a fresh random id at table 0.
+    pub fn new() -> Self {
+        Game::with_id(random_game_id(), 0)
+    }
+
+    pub fn with_id(id: GameId, table: TableId) -> Self {
+        Game {
+            id,
+            table,
+            players: Vec::new(),
+            round: 0,
+            beacon: None,
+        }
+    }
+
+    pub fn id(&self) -> &GameId {
+        &self.id
+    }
+
+    pub fn table(&self) -> TableId {
+        self.table
+    }
+
+    pub fn add_player(&mut self, name: impl Into<String>) -> &mut Player {
+        self.seat_player(name, Player::new())
+    }
+
+    // Seats a player with an existing identity, e.g. one loaded from a keystore.
+    pub fn seat_player(&mut self, name: impl Into<String>, player: Player) -> &mut Player {
+        self.players.push((name.into(), player));
+        &mut self.players.last_mut().unwrap().1
+    }
+
+    pub fn player(&self, name: &str) -> Option<&Player> {
+        self.players.iter().find(|(n, _)| n == name).map(|(_, p)| p)
+    }
+
+    pub fn player_mut(&mut self, name: &str) -> Option<&mut Player> {
+        self.players
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, p)| p)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = (&str, &Player)> {
+        self.players.iter().map(|(n, p)| (n.as_str(), p))
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    // An inbox that accepts signed messages from the current players, by seat.
+    pub fn inbox(&self) -> Inbox {
+        Inbox::new(
+            self.id,
+            self.players.iter().map(|(_, p)| p.public_key()).collect(),
+        )
+    }
+
+    pub fn set_beacon(&mut self, beacon: impl RandomnessBeacon + 'static) {
+        self.beacon = Some(Box::new(beacon));
+    }
+
+    // The input for `round`, from the game's beacon if it has one.
+    pub fn round_input(&mut self, round: u32) -> Result<RoundInput, BeaconError> {
+        match &mut self.beacon {
+            Some(beacon) => beacon.round_input(round),
+            None => Ok(self.commit_reveal()?),
+        }
+    }
+
+    // Runs commit-reveal among every player to agree on the next round input.
+    pub fn commit_reveal(&mut self) -> Result<[u8; 32], CommitError> {
+        let mut session =
+            CommitReveal::new(self.players.iter().map(|(_, p)| p.public_key()).collect())?;
+        for (_, player) in self.players.iter_mut() {
+            let commitment = player.commit_contribution();
+            session.commit(&player.public_key(), commitment)?;
+        }
+        for (_, player) in &self.players {
+            if let Some(contribution) = player.contribution() {
+                session.reveal(&player.public_key(), contribution)?;
+            }
+        }
+        session.finish()
+    }
+
+    // Starts a hand of Texas Hold'em, dealing two hole cards to every player. Fails if
+    // one deck cannot serve the table.
+    pub fn deal_holdem(&mut self, input: &[u8]) -> Result<Holdem, PokerError> {
+        self.round += 1;
+        Holdem::deal(self.id, self.table, self.round, input, &mut self.players)
+    }
+
+    pub fn play_round(&mut self, input: &[u8]) -> Round {
+        self.round += 1;
+        let context = DrawContext::new(self.id, self.table, self.round, CardSlot::Hole(0), input);
+
+        // Players draw their cards
+        for (_, player) in self.players.iter_mut() {
+            player.draw_card(&context);
+        }
+
+        // Verify every draw in one batch, then reveal the cards from a fresh deck in
+        // seating order
+        let claims: Vec<_> = self
+            .players
+            .iter()
+            .map(|(_, player)| player.claim(&context))
+            .collect();
+        let mut verified = verify_each(claims.iter().flatten()).into_iter();
+        let mut deck = Deck::new();
+        let draws: Vec<Draw> = self
+            .players
+            .iter()
+            .zip(&claims)
+            .map(|((name, player), claim)| {
+                let card = match claim {
+                    Ok(_) if verified.next() == Some(true) => player.reveal_card_from(&mut deck),
+                    Ok(_) => Err(PokerError::InvalidProof),
+                    Err(err) => Err(err.clone()),
+                };
+                Draw {
+                    player: name.clone(),
+                    card,
+                }
+            })
+            .collect();
+
+        let winner = draws
+            .iter()
+            .filter_map(|draw| draw.card.as_ref().ok().map(|card| (card, &draw.player)))
+            .max_by_key(|(card, _)| *card)
+            .map(|(_, player)| player.clone());
+
+        Round {
+            number: self.round,
+            input: input.to_vec(),
+            draws,
+            winner,
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::SeededBeacon;
+
+    #[test]
+    fn test_play_round() {
+        let mut game = Game::new();
+        game.add_player("Alice");
+        game.add_player("Bob");
+
+        let round = game.play_round(b"round input");
+        assert_eq!(round.number, 1);
+        assert_eq!(round.draws.len(), 2);
+        assert!(round.draws.iter().all(|draw| draw.card.is_ok()));
+
+        let best = round
+            .draws
+            .iter()
+            .max_by_key(|draw| draw.card.clone().ok())
+            .unwrap();
+        assert_eq!(round.winner.as_ref(), Some(&best.player));
+    }
+
+    #[test]
+    fn test_round_cards_are_distinct() {
+        let mut game = Game::new();
+        for name in ["Alice", "Bob", "Carol", "Dave", "Eve", "Frank"] {
+            game.add_player(name);
+        }
+        for i in 0..20u8 {
+            let round = game.play_round(&[i]);
+            let mut cards: Vec<Card> = round
+                .draws
+                .iter()
+                .map(|draw| draw.card.clone().unwrap())
+                .collect();
+            cards.sort();
+            cards.dedup();
+            assert_eq!(cards.len(), 6);
+        }
+    }
+
+    #[test]
+    fn test_commit_reveal_input() {
+        let mut game = Game::new();
+        game.add_player("Alice");
+        game.add_player("Bob");
+        let first = game.commit_reveal().unwrap();
+        let second = game.commit_reveal().unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_seeded_beacon_replays_game() {
+        let deal = || {
+            let mut game = Game::with_id([4; 32], 0);
+            game.seat_player("Alice", Player::from_seed(&[1; 32]));
+            game.seat_player("Bob", Player::from_seed(&[2; 32]));
+            game.set_beacon(SeededBeacon::new([5; 32]));
+            let input = game.round_input(game.round() + 1).unwrap();
+            game.deal_holdem(&input).unwrap()
+        };
+        let (first, second) = (deal(), deal());
+        assert_eq!(first.input(), second.input());
+        // VRF proofs are randomised, but the cards they select are not.
+        for (a, b) in first.seats().iter().zip(second.seats()) {
+            assert_eq!(a.hole_cards, b.hole_cards);
+        }
+    }
+
+    #[test]
+    fn test_round_counter() {
+        let mut game = Game::new();
+        game.add_player("Alice");
+        game.play_round(b"one");
+        let round = game.play_round(b"two");
+        assert_eq!(round.number, 2);
+        assert_eq!(game.round(), 2);
+    }
+
+    #[test]
+    fn test_empty_game_has_no_winner() {
+        let mut game = Game::new();
+        let round = game.play_round(b"input");
+        assert!(round.draws.is_empty());
+        assert!(round.winner.is_none());
+    }
+}
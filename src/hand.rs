@@ -0,0 +1,254 @@
+use crate::card::{Card, Rank};
+use crate::error::PokerError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Category {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Category::HighCard => "high card",
+            Category::OnePair => "one pair",
+            Category::TwoPair => "two pair",
+            Category::ThreeOfAKind => "three of a kind",
+            Category::Straight => "straight",
+            Category::Flush => "flush",
+            Category::FullHouse => "full house",
+            Category::FourOfAKind => "four of a kind",
+            Category::StraightFlush => "straight flush",
+        };
+        f.write_str(name)
+    }
+}
+
+// The strength of a five-card hand. `kickers` lists the ranks that decide between hands
+// of the same category, most significant first (e.g. trips then pair for a full house,
+// or just the top card for a straight), so the derived ordering compares hands the way
+// poker does and equal values are exact ties.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HandRank {
+    pub category: Category,
+    pub kickers: Vec<Rank>,
+}
+
+pub fn evaluate_five(cards: &[Card; 5]) -> HandRank {
+    let mut counts = [0u8; 15];
+    for card in cards {
+        counts[card.rank.value() as usize] += 1;
+    }
+
+    // Ranks grouped by how often they appear, larger groups and then higher ranks first.
+    let mut groups: Vec<(u8, Rank)> = Rank::ALL
+        .iter()
+        .filter(|rank| counts[rank.value() as usize] > 0)
+        .map(|&rank| (counts[rank.value() as usize], rank))
+        .collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    let ranks: Vec<Rank> = groups.iter().map(|&(_, rank)| rank).collect();
+
+    let flush = cards.iter().all(|card| card.suit == cards[0].suit);
+    let straight_high = if groups.len() == 5 {
+        if ranks[0].value() - ranks[4].value() == 4 {
+            Some(ranks[0])
+        } else if ranks == [Rank::Ace, Rank::Five, Rank::Four, Rank::Three, Rank::Two] {
+            // The wheel: the ace plays low.
+            Some(Rank::Five)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let category = match (
+        straight_high,
+        flush,
+        groups[0].0,
+        groups.get(1).map(|g| g.0),
+    ) {
+        (Some(_), true, _, _) => Category::StraightFlush,
+        (_, _, 4, _) => Category::FourOfAKind,
+        (_, _, 3, Some(2)) => Category::FullHouse,
+        (_, true, _, _) => Category::Flush,
+        (Some(_), _, _, _) => Category::Straight,
+        (_, _, 3, _) => Category::ThreeOfAKind,
+        (_, _, 2, Some(2)) => Category::TwoPair,
+        (_, _, 2, _) => Category::OnePair,
+        _ => Category::HighCard,
+    };
+
+    let kickers = match straight_high {
+        Some(high) if matches!(category, Category::Straight | Category::StraightFlush) => {
+            vec![high]
+        }
+        _ => ranks,
+    };
+
+    HandRank { category, kickers }
+}
+
+// Finds the best five-card hand among 5 to 7 distinct cards, returning its rank and the
+// cards that make it.
+pub fn best_hand(cards: &[Card]) -> Result<(HandRank, [Card; 5]), PokerError> {
+    if !(5..=7).contains(&cards.len()) {
+        return Err(PokerError::WrongCardCount(cards.len()));
+    }
+    for (i, card) in cards.iter().enumerate() {
+        if cards[..i].contains(card) {
+            return Err(PokerError::DuplicateCard(*card));
+        }
+    }
+    let best = (0u32..1 << cards.len())
+        .filter(|mask| mask.count_ones() == 5)
+        .map(|mask| {
+            let mut hand = [cards[0]; 5];
+            let chosen = cards
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0);
+            for (slot, (_, &card)) in hand.iter_mut().zip(chosen) {
+                *slot = card;
+            }
+            (evaluate_five(&hand), hand)
+        })
+        .max_by(|a, b| a.0.cmp(&b.0));
+    Ok(best.expect("at least one five-card hand"))
+}
+
+pub fn evaluate(cards: &[Card]) -> Result<HandRank, PokerError> {
+    best_hand(cards).map(|(rank, _)| rank)
+}
+
+// Indices of every hand that ties for the best rank.
+pub fn winners(ranks: &[HandRank]) -> Vec<usize> {
+    match ranks.iter().max() {
+        Some(best) => (0..ranks.len()).filter(|&i| &ranks[i] == best).collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn five(s: &str) -> HandRank {
+        evaluate_five(&cards(s).try_into().unwrap())
+    }
+
+    #[test]
+    fn test_categories() {
+        let cases = [
+            ("As Kd 9h 7c 3s", Category::HighCard),
+            ("As Ad 9h 7c 3s", Category::OnePair),
+            ("As Ad 9h 9c 3s", Category::TwoPair),
+            ("As Ad Ah 7c 3s", Category::ThreeOfAKind),
+            ("9s Td Jh Qc Ks", Category::Straight),
+            ("As 2d 3h 4c 5s", Category::Straight),
+            ("As Ks 9s 7s 3s", Category::Flush),
+            ("As Ad Ah 7c 7s", Category::FullHouse),
+            ("As Ad Ah Ac 3s", Category::FourOfAKind),
+            ("9h Th Jh Qh Kh", Category::StraightFlush),
+            ("Ah 2h 3h 4h 5h", Category::StraightFlush),
+        ];
+        for (hand, category) in cases {
+            assert_eq!(five(hand).category, category, "{}", hand);
+        }
+    }
+
+    #[test]
+    fn test_wheel_is_lowest_straight() {
+        let wheel = five("As 2d 3h 4c 5s");
+        let six_high = five("2d 3h 4c 5s 6s");
+        assert_eq!(wheel.kickers, vec![Rank::Five]);
+        assert!(wheel < six_high);
+        assert_eq!(five("Qs Kd Ah 2c 3s").category, Category::HighCard);
+    }
+
+    #[test]
+    fn test_kickers_break_ties() {
+        assert!(five("As Ad Kh 7c 3s") > five("As Ad Qh Jc 9s"));
+        assert!(five("Ks Kd 4h 4c As") > five("Ks Kd 4h 4c Qs"));
+        assert!(five("3s 3d 3h Ac Ad") < five("4s 4d 4h 2c 2d"));
+        assert!(five("As Ks 9s 7s 3s") > five("As Ks 9s 7s 2s"));
+    }
+
+    #[test]
+    fn test_exact_tie_ignores_suits() {
+        assert_eq!(five("As Kd 9h 7c 3s"), five("Ad Kh 9c 7s 3d"));
+        assert_eq!(
+            winners(&[five("As Kd 9h 7c 3s"), five("Ad Kh 9c 7s 3d")]),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_best_of_seven() {
+        let (rank, hand) = best_hand(&cards("2c 7h Ah Kh Qh Jh Th")).unwrap();
+        assert_eq!(rank.category, Category::StraightFlush);
+        assert_eq!(rank.kickers, vec![Rank::Ace]);
+        assert!(!hand.contains(&"2c".parse().unwrap()));
+
+        let rank = evaluate(&cards("As Ad Ah Kc Kd Qs Qh")).unwrap();
+        assert_eq!(rank.category, Category::FullHouse);
+        assert_eq!(rank.kickers, vec![Rank::Ace, Rank::King]);
+
+        let rank = evaluate(&cards("9s 9d 5h 5c 3d 3s Ah")).unwrap();
+        assert_eq!(rank.category, Category::TwoPair);
+        assert_eq!(rank.kickers, vec![Rank::Nine, Rank::Five, Rank::Ace]);
+    }
+
+    #[test]
+    fn test_board_plays() {
+        let board = "Ts Js Qs Ks As";
+        let alice = evaluate(&cards(&format!("{} 2c 3d", board))).unwrap();
+        let bob = evaluate(&cards(&format!("{} 4c 5d", board))).unwrap();
+        assert_eq!(alice, bob);
+    }
+
+    #[test]
+    fn test_wrong_number_of_cards() {
+        assert_eq!(
+            evaluate(&cards("As Kd 9h 7c")),
+            Err(PokerError::WrongCardCount(4))
+        );
+        assert_eq!(
+            evaluate(&cards("As Kd 9h 7c 3s 2d 4h 5c")),
+            Err(PokerError::WrongCardCount(8))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_cards() {
+        assert_eq!(
+            evaluate(&cards("As Kd 9h 7c 9h")),
+            Err(PokerError::DuplicateCard("9h".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_winners() {
+        let ranks = [
+            five("As Ad 9h 7c 3s"),
+            five("Ks Kd Kh 7c 3s"),
+            five("2s 2d 9h 7c 3s"),
+        ];
+        assert_eq!(winners(&ranks), vec![1]);
+        assert!(winners(&[]).is_empty());
+    }
+}
@@ -0,0 +1,706 @@
+use crate::betting::{Betting, Chips};
+use crate::card::Card;
+use crate::deck::Deck;
+use crate::error::PokerError;
+use crate::hand::{best_hand, winners, HandRank};
+use crate::player::Player;
+use crate::pot::{build_pots, distribute, Pot};
+use crate::seed::JointSeed;
+use crate::transcript::{CardSlot, DrawContext, GameId, TableId};
+use crate::verify::{verify_claims, verify_each, CardClaim, InvalidClaims};
+use rand_chacha::ChaChaRng;
+use schnorrkel::PublicKey;
+
+// A deal needs two hole cards per player and five community cards from one deck.
+pub const MAX_PLAYERS: usize = 23;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seat {
+    pub player: String,
+    pub hole_cards: Vec<Card>,
+    // The VRF draws behind the hole cards, in dealing order.
+    pub claims: Vec<CardClaim>,
+    // Whether every VRF draw behind the hole cards verified against the hand input.
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShowdownHand {
+    pub player: String,
+    pub rank: HandRank,
+    pub cards: [Card; 5],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Showdown {
+    pub hands: Vec<ShowdownHand>,
+    // Every player holding the best hand; more than one means the pot is split.
+    pub winners: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    pub showdown: Showdown,
+    pub pots: Vec<Pot>,
+    // Chips won by each seat, in seating order.
+    pub payouts: Vec<Chips>,
+}
+
+// One hand of Texas Hold'em: hole cards are dealt up front by each player's VRF, and the
+// flop, turn and river come off the same deck as the hand advances.
+#[derive(Debug, Clone)]
+pub struct Holdem {
+    game_id: GameId,
+    table_id: TableId,
+    number: u32,
+    input: Vec<u8>,
+    deck: Deck,
+    board_rng: ChaChaRng,
+    board_claims: Vec<CardClaim>,
+    seats: Vec<Seat>,
+    board: Vec<Card>,
+}
+
+impl Holdem {
+    pub(crate) fn deal(
+        game_id: GameId,
+        table_id: TableId,
+        number: u32,
+        input: &[u8],
+        players: &mut [(String, Player)],
+    ) -> Result<Self, PokerError> {
+        if players.len() > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers);
+        }
+        let context = |slot| DrawContext::new(game_id, table_id, number, slot, input);
+        let mut deck = Deck::new();
+        let mut seats: Vec<Seat> = players
+            .iter()
+            .map(|(name, _)| Seat {
+                player: name.clone(),
+                hole_cards: Vec::new(),
+                claims: Vec::new(),
+                valid: true,
+            })
+            .collect();
+
+        // Deal one card to everyone in seating order, then the second. Each slot is drawn
+        // under its own context so a player's two cards come from independent VRF outputs.
+        for slot in 0..2 {
+            let hole_context = context(CardSlot::Hole(slot));
+            for ((_, player), seat) in players.iter_mut().zip(seats.iter_mut()) {
+                player.draw_card(&hole_context);
+                match player.claim(&hole_context) {
+                    Ok(claim) => seat.claims.push(claim),
+                    Err(_) => seat.valid = false,
+                }
+                seat.hole_cards.push(player.reveal_card_from(&mut deck)?);
+            }
+        }
+
+        // Check every hole card draw at the table in one batch.
+        let mut verified = verify_each(seats.iter().flat_map(|seat| &seat.claims)).into_iter();
+        for seat in seats.iter_mut() {
+            let failures = verified
+                .by_ref()
+                .take(seat.claims.len())
+                .filter(|ok| !ok)
+                .count();
+            seat.valid &= failures == 0;
+        }
+
+        // Community cards come from every player's VRF output together, so nobody can
+        // predict or steer them alone.
+        let board_context = context(CardSlot::Board);
+        let mut board_claims = Vec::new();
+        let mut board_seats = Vec::new();
+        for (i, ((_, player), seat)) in players.iter_mut().zip(seats.iter_mut()).enumerate() {
+            player.draw_card(&board_context);
+            match player.claim(&board_context) {
+                Ok(claim) => {
+                    board_claims.push(claim);
+                    board_seats.push(i);
+                }
+                Err(_) => seat.valid = false,
+            }
+        }
+        let mut joint = JointSeed::new();
+        if let Err(InvalidClaims(failed)) = joint.add_claims(&board_claims) {
+            for i in failed {
+                seats[board_seats[i]].valid = false;
+            }
+        }
+
+        Ok(Holdem {
+            game_id,
+            table_id,
+            number,
+            input: input.to_vec(),
+            deck,
+            board_rng: joint.rng(),
+            board_claims,
+            seats,
+            board: Vec::new(),
+        })
+    }
+
+    // Rebuilds a hand from every player's published draws, as a table that holds no
+    // secret keys would. `hole_claims[seat]` are that seat's two hole card draws in slot
+    // order. Every claim must come from its seat's key, be made for this hand and verify.
+    pub fn from_claims(
+        game_id: GameId,
+        table_id: TableId,
+        number: u32,
+        input: &[u8],
+        players: &[(String, PublicKey)],
+        hole_claims: &[[CardClaim; 2]],
+        board_claims: &[CardClaim],
+    ) -> Result<Self, PokerError> {
+        if players.len() > MAX_PLAYERS {
+            return Err(PokerError::TooManyPlayers);
+        }
+        if hole_claims.len() != players.len() || board_claims.len() != players.len() {
+            return Err(PokerError::NotDrawn);
+        }
+        let context = |slot| DrawContext::new(game_id, table_id, number, slot, input);
+        let check = |claim: &CardClaim, public: &PublicKey, slot| {
+            if &claim.public != public {
+                Err(PokerError::UnknownPlayer)
+            } else if claim.context != context(slot) {
+                Err(PokerError::InputMismatch)
+            } else {
+                Ok(())
+            }
+        };
+
+        let mut deck = Deck::new();
+        let mut seats: Vec<Seat> = players
+            .iter()
+            .map(|(name, _)| Seat {
+                player: name.clone(),
+                hole_cards: Vec::new(),
+                claims: Vec::new(),
+                valid: true,
+            })
+            .collect();
+        for slot in 0..2 {
+            for ((seat, (_, public)), claims) in seats.iter_mut().zip(players).zip(hole_claims) {
+                let claim = &claims[slot as usize];
+                check(claim, public, CardSlot::Hole(slot))?;
+                seat.hole_cards.push(claim.card_from(&mut deck)?);
+                seat.claims.push(claim.clone());
+            }
+        }
+
+        let mut joint = JointSeed::new();
+        for (claim, (_, public)) in board_claims.iter().zip(players) {
+            check(claim, public, CardSlot::Board)?;
+            joint.add_claim(claim)?;
+        }
+
+        Ok(Holdem {
+            game_id,
+            table_id,
+            number,
+            input: input.to_vec(),
+            deck,
+            board_rng: joint.rng(),
+            board_claims: board_claims.to_vec(),
+            seats,
+            board: Vec::new(),
+        })
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn table_id(&self) -> TableId {
+        self.table_id
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    // The context every draw for `slot` in this hand must have been made under.
+    pub fn draw_context(&self, slot: CardSlot) -> DrawContext {
+        DrawContext::new(self.game_id, self.table_id, self.number, slot, &self.input)
+    }
+
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    // Every VRF draw made for this hand: hole cards seat by seat, then the board draws.
+    pub fn claims(&self) -> impl Iterator<Item = &CardClaim> {
+        self.seats
+            .iter()
+            .flat_map(|seat| &seat.claims)
+            .chain(&self.board_claims)
+    }
+
+    // Every player's VRF draw towards the community cards.
+    pub fn board_claims(&self) -> &[CardClaim] {
+        &self.board_claims
+    }
+
+    // Re-checks the whole deal from the published claims alone: every proof must verify
+    // against this hand's draw contexts, and replaying the draws must give the same hole cards
+    // and community cards.
+    pub fn audit(&self) -> Result<(), PokerError> {
+        let mut seen = Vec::new();
+        for &card in self
+            .seats
+            .iter()
+            .flat_map(|seat| &seat.hole_cards)
+            .chain(&self.board)
+        {
+            if seen.contains(&card) {
+                return Err(PokerError::DuplicateCard(card));
+            }
+            seen.push(card);
+        }
+
+        // Every hole card claim must be for its own slot in this hand, in dealing order.
+        let mut dealt = Vec::new();
+        for slot in 0..2 {
+            let hole_context = self.draw_context(CardSlot::Hole(slot as u8));
+            for seat in &self.seats {
+                match seat.claims.get(slot) {
+                    Some(claim) if claim.context == hole_context => {
+                        dealt.push((claim, seat.hole_cards.get(slot)))
+                    }
+                    Some(_) => return Err(PokerError::InputMismatch),
+                    None => return Err(PokerError::NotDrawn),
+                }
+            }
+        }
+        let outputs = verify_claims(dealt.iter().map(|(claim, _)| *claim))
+            .map_err(|_| PokerError::InvalidProof)?;
+        let mut deck = Deck::new();
+        for ((_, card), output) in dealt.iter().zip(&outputs) {
+            if deck.draw(output).as_ref() != *card {
+                return Err(PokerError::CardMismatch);
+            }
+        }
+
+        let board_context = self.draw_context(CardSlot::Board);
+        if self
+            .board_claims
+            .iter()
+            .any(|claim| claim.context != board_context)
+        {
+            return Err(PokerError::InputMismatch);
+        }
+        let mut joint = JointSeed::new();
+        joint
+            .add_claims(&self.board_claims)
+            .map_err(|_| PokerError::InvalidProof)?;
+        if joint.len() != self.seats.len() {
+            return Err(PokerError::NotDrawn);
+        }
+        let mut rng = joint.rng();
+        if self
+            .board
+            .iter()
+            .all(|&card| deck.draw_with(&mut rng) == Some(card))
+        {
+            Ok(())
+        } else {
+            Err(PokerError::CardMismatch)
+        }
+    }
+
+    pub fn street(&self) -> Street {
+        match self.board.len() {
+            0 => Street::Preflop,
+            3 => Street::Flop,
+            4 => Street::Turn,
+            _ => Street::River,
+        }
+    }
+
+    // Deals the flop, turn or river, whichever comes next. Returns `None` once the river
+    // is out.
+    pub fn deal_next_street(&mut self) -> Option<Street> {
+        let count = match self.street() {
+            Street::Preflop => 3,
+            Street::Flop | Street::Turn => 1,
+            Street::River => return None,
+        };
+        for _ in 0..count {
+            let card = self.deck.draw_with(&mut self.board_rng)?;
+            self.board.push(card);
+        }
+        Some(self.street())
+    }
+
+    // Deals any remaining community cards and ranks every valid hand.
+    pub fn showdown(&mut self) -> Showdown {
+        self.showdown_among(&vec![true; self.seats.len()]).0
+    }
+
+    // Pays out the pots once betting has finished. Players who folded do not show their
+    // cards, and a player left alone in the hand wins without the board being run out.
+    pub fn settle(&mut self, betting: &Betting) -> Result<Settlement, PokerError> {
+        if betting.seats().len() != self.seats.len() {
+            return Err(PokerError::SeatMismatch);
+        }
+
+        let in_hand: Vec<bool> = betting.seats().iter().map(|seat| !seat.folded).collect();
+        let (showdown, ranks) = if betting.is_hand_over() {
+            let winners = (0..self.seats.len())
+                .filter(|&i| in_hand[i])
+                .map(|i| self.seats[i].player.clone())
+                .collect();
+            let showdown = Showdown {
+                hands: Vec::new(),
+                winners,
+            };
+            (showdown, vec![None; self.seats.len()])
+        } else {
+            self.showdown_among(&in_hand)
+        };
+
+        let pots = build_pots(betting.seats());
+        let payouts = distribute(&pots, &ranks, betting.dealer());
+        Ok(Settlement {
+            showdown,
+            pots,
+            payouts,
+        })
+    }
+
+    // Ranks the valid hands of the seats still in the hand, also returning each seat's
+    // rank by position.
+    fn showdown_among(&mut self, in_hand: &[bool]) -> (Showdown, Vec<Option<HandRank>>) {
+        while self.deal_next_street().is_some() {}
+
+        let ranks: Vec<Option<(HandRank, [Card; 5])>> = self
+            .seats
+            .iter()
+            .zip(in_hand)
+            .map(|(seat, &in_hand)| {
+                if !in_hand || !seat.valid || seat.hole_cards.len() != 2 {
+                    return None;
+                }
+                let cards: Vec<Card> = seat.hole_cards.iter().chain(&self.board).copied().collect();
+                best_hand(&cards).ok()
+            })
+            .collect();
+
+        let hands: Vec<ShowdownHand> = self
+            .seats
+            .iter()
+            .zip(&ranks)
+            .filter_map(|(seat, rank)| {
+                rank.clone().map(|(rank, cards)| ShowdownHand {
+                    player: seat.player.clone(),
+                    rank,
+                    cards,
+                })
+            })
+            .collect();
+
+        let hand_ranks: Vec<HandRank> = hands.iter().map(|hand| hand.rank.clone()).collect();
+        let winners = winners(&hand_ranks)
+            .into_iter()
+            .map(|i| hands[i].player.clone())
+            .collect();
+
+        let ranks = ranks
+            .into_iter()
+            .map(|rank| rank.map(|(rank, _)| rank))
+            .collect();
+        (Showdown { hands, winners }, ranks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::Action;
+    use crate::game::Game;
+
+    fn game(players: usize) -> Game {
+        let mut game = Game::new();
+        for i in 0..players {
+            game.add_player(format!("Player {}", i));
+        }
+        game
+    }
+
+    #[test]
+    fn test_deal_hole_cards() {
+        let mut game = game(4);
+        let hand = game.deal_holdem(b"hand input").unwrap();
+        assert_eq!(hand.street(), Street::Preflop);
+        assert!(hand.board().is_empty());
+        assert!(hand
+            .seats()
+            .iter()
+            .all(|seat| seat.valid && seat.hole_cards.len() == 2));
+        assert_eq!(hand.deck().len(), 52 - 8);
+    }
+
+    #[test]
+    fn test_streets() {
+        let mut game = game(2);
+        let mut hand = game.deal_holdem(b"hand input").unwrap();
+        assert_eq!(hand.deal_next_street(), Some(Street::Flop));
+        assert_eq!(hand.board().len(), 3);
+        assert_eq!(hand.deal_next_street(), Some(Street::Turn));
+        assert_eq!(hand.board().len(), 4);
+        assert_eq!(hand.deal_next_street(), Some(Street::River));
+        assert_eq!(hand.board().len(), 5);
+        assert_eq!(hand.deal_next_street(), None);
+        assert_eq!(hand.board().len(), 5);
+    }
+
+    #[test]
+    fn test_all_cards_distinct() {
+        let mut full = game(MAX_PLAYERS);
+        let mut hand = full.deal_holdem(b"full table").unwrap();
+        hand.showdown();
+        let mut cards: Vec<Card> = hand
+            .seats()
+            .iter()
+            .flat_map(|seat| seat.hole_cards.clone())
+            .chain(hand.board().iter().copied())
+            .collect();
+        assert_eq!(cards.len(), 52 - 1);
+        cards.sort();
+        cards.dedup();
+        assert_eq!(cards.len(), 52 - 1);
+
+        let mut crowded = game(MAX_PLAYERS + 1);
+        assert_eq!(
+            crowded.deal_holdem(b"full table").err(),
+            Some(PokerError::TooManyPlayers)
+        );
+    }
+
+    fn seeded_game(table_id: TableId) -> Game {
+        let mut game = Game::with_id([1; 32], table_id);
+        for i in 0..3u8 {
+            game.seat_player(format!("Player {}", i), Player::from_seed(&[i; 32]));
+        }
+        game
+    }
+
+    fn full_board(game: &mut Game, input: &[u8]) -> Vec<Card> {
+        let mut hand = game.deal_holdem(input).unwrap();
+        hand.showdown();
+        hand.board().to_vec()
+    }
+
+    #[test]
+    fn test_board_is_reproducible() {
+        let first = full_board(&mut seeded_game(0), b"same input");
+        assert_eq!(full_board(&mut seeded_game(0), b"same input"), first);
+        assert_ne!(full_board(&mut seeded_game(0), b"other input"), first);
+    }
+
+    #[test]
+    fn test_deal_is_bound_to_table_and_round() {
+        let first = full_board(&mut seeded_game(0), b"same input");
+        assert_ne!(full_board(&mut seeded_game(1), b"same input"), first);
+
+        let mut game = seeded_game(0);
+        full_board(&mut game, b"same input");
+        assert_ne!(full_board(&mut game, b"same input"), first);
+    }
+
+    #[test]
+    fn test_claims_do_not_transfer_between_slots() {
+        let mut game = game(2);
+        let hand = game.deal_holdem(b"slots").unwrap();
+        let mut claim = hand.seats()[0].claims[0].clone();
+        assert!(claim.is_valid());
+        claim.context = hand.draw_context(CardSlot::Hole(1));
+        assert!(!claim.is_valid());
+        claim.context = hand.draw_context(CardSlot::Board);
+        assert!(!claim.is_valid());
+
+        let mut moved = hand.clone();
+        moved.seats[0].claims.swap(0, 1);
+        assert_eq!(moved.audit(), Err(PokerError::InputMismatch));
+    }
+
+    #[test]
+    fn test_audit_from_claims() {
+        let mut game = game(3);
+        let mut hand = game.deal_holdem(b"audit").unwrap();
+        assert_eq!(hand.audit(), Ok(()));
+        hand.showdown();
+        assert_eq!(hand.audit(), Ok(()));
+        assert!(hand.seats().iter().all(|seat| seat.claims.len() == 2));
+        assert_eq!(hand.board_claims().len(), 3);
+
+        let mut swapped = hand.clone();
+        swapped.seats[0].hole_cards.swap(0, 1);
+        assert_eq!(swapped.audit(), Err(PokerError::CardMismatch));
+
+        let mut forged = hand.clone();
+        forged.seats[1].claims[0] = forged.seats[2].claims[0].clone();
+        assert_eq!(forged.audit(), Err(PokerError::CardMismatch));
+
+        let mut missing = hand.clone();
+        missing.board_claims.pop();
+        assert_eq!(missing.audit(), Err(PokerError::NotDrawn));
+
+        let mut wrong_board = hand.clone();
+        wrong_board.board.swap(0, 4);
+        assert_eq!(wrong_board.audit(), Err(PokerError::CardMismatch));
+
+        let mut duplicated = hand.clone();
+        duplicated.seats[0].hole_cards[1] = duplicated.board[2];
+        assert_eq!(
+            duplicated.audit(),
+            Err(PokerError::DuplicateCard(duplicated.board[2]))
+        );
+    }
+
+    #[test]
+    fn test_batch_verify_hand_history() {
+        let mut game = game(4);
+        let mut history: Vec<Holdem> = (0..5u8).map(|i| game.deal_holdem(&[i]).unwrap()).collect();
+        assert_eq!(
+            verify_claims(history.iter().flat_map(Holdem::claims))
+                .unwrap()
+                .len(),
+            5 * 4 * 3
+        );
+
+        // A claim replayed from an earlier hand still carries that hand's context, so the
+        // audit catches it; relabelling it with the later context breaks its proof.
+        let mut replayed = history[0].seats[2].claims[1].clone();
+        history[3].seats[2].claims[1] = replayed.clone();
+        assert_eq!(history[3].audit(), Err(PokerError::InputMismatch));
+        replayed.context = history[3].draw_context(CardSlot::Hole(1));
+        history[3].seats[2].claims[1] = replayed;
+        assert_eq!(
+            verify_claims(history.iter().flat_map(Holdem::claims)),
+            Err(InvalidClaims(vec![3 * 12 + 2 * 2 + 1]))
+        );
+    }
+
+    #[test]
+    fn test_rebuild_from_claims() {
+        let mut game = game(3);
+        let hand = game.deal_holdem(b"rebuild").unwrap();
+        let players: Vec<(String, PublicKey)> = game
+            .players()
+            .map(|(name, player)| (name.to_string(), player.public_key()))
+            .collect();
+        let hole: Vec<[CardClaim; 2]> = hand
+            .seats()
+            .iter()
+            .map(|seat| [seat.claims[0].clone(), seat.claims[1].clone()])
+            .collect();
+        let rebuild = |hole: &[[CardClaim; 2]], board: &[CardClaim]| {
+            Holdem::from_claims(
+                *hand.game_id(),
+                hand.table_id(),
+                hand.number(),
+                hand.input(),
+                &players,
+                hole,
+                board,
+            )
+        };
+
+        let mut rebuilt = rebuild(&hole, hand.board_claims()).unwrap();
+        assert_eq!(rebuilt.seats(), hand.seats());
+        assert_eq!(rebuilt.showdown(), hand.clone().showdown());
+
+        let mut stolen = hole.clone();
+        stolen[0][0] = hole[1][0].clone();
+        assert_eq!(
+            rebuild(&stolen, hand.board_claims()).err(),
+            Some(PokerError::UnknownPlayer)
+        );
+        let mut swapped = hole.clone();
+        swapped[2].swap(0, 1);
+        assert_eq!(
+            rebuild(&swapped, hand.board_claims()).err(),
+            Some(PokerError::InputMismatch)
+        );
+        assert_eq!(
+            rebuild(&hole, &hand.board_claims()[1..]).err(),
+            Some(PokerError::NotDrawn)
+        );
+    }
+
+    #[test]
+    fn test_showdown() {
+        let mut game = game(3);
+        let mut hand = game.deal_holdem(b"showdown").unwrap();
+        let showdown = hand.showdown();
+        assert_eq!(showdown.hands.len(), 3);
+        assert!(!showdown.winners.is_empty());
+
+        let best = showdown.hands.iter().map(|hand| &hand.rank).max().unwrap();
+        for hand in &showdown.hands {
+            assert_eq!(showdown.winners.contains(&hand.player), &hand.rank == best);
+        }
+    }
+
+    #[test]
+    fn test_settle_after_folds() {
+        let mut game = game(3);
+        let mut hand = game.deal_holdem(b"folds").unwrap();
+        let mut betting = Betting::new(&[100, 100, 100], 0, 1, 2).unwrap();
+        betting.act(0, Action::Raise(10)).unwrap();
+        betting.act(1, Action::Fold).unwrap();
+        betting.act(2, Action::Fold).unwrap();
+
+        let other_table = Betting::new(&[100, 100], 0, 1, 2).unwrap();
+        assert_eq!(hand.settle(&other_table), Err(PokerError::SeatMismatch));
+
+        let settlement = hand.settle(&betting).unwrap();
+        assert_eq!(hand.street(), Street::Preflop);
+        assert!(settlement.showdown.hands.is_empty());
+        assert_eq!(settlement.showdown.winners, vec!["Player 0".to_string()]);
+        assert_eq!(settlement.payouts, vec![13, 0, 0]);
+    }
+
+    #[test]
+    fn test_settle_all_in_showdown() {
+        let mut game = game(3);
+        let mut hand = game.deal_holdem(b"all in").unwrap();
+        let mut betting = Betting::new(&[100, 50, 200], 0, 1, 2).unwrap();
+        betting.act(0, Action::AllIn).unwrap();
+        betting.act(1, Action::AllIn).unwrap();
+        betting.act(2, Action::Call).unwrap();
+        assert!(betting.is_round_complete());
+
+        let settlement = hand.settle(&betting).unwrap();
+        assert_eq!(hand.board().len(), 5);
+        assert_eq!(settlement.showdown.hands.len(), 3);
+        assert_eq!(settlement.pots.len(), 2);
+        assert_eq!(settlement.payouts.iter().sum::<Chips>(), betting.pot());
+        assert!(settlement.payouts[1] <= 150);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::transcript::GameId;
+use crate::wire::{Message, SignedMessage, WireError};
+use schnorrkel::PublicKey;
+use std::cmp::Ordering;
+use std::fmt;
+
+// Seats are identified by their index in the inbox's player list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InboxError {
+    // The message could not be decoded as a signed message, or its signature is bad.
+    Wire(WireError),
+    UnknownSender,
+    WrongGame,
+    Replayed {
+        seat: usize,
+        sequence: u64,
+    },
+    OutOfOrder {
+        seat: usize,
+        expected: u64,
+        got: u64,
+    },
+}
+
+impl fmt::Display for InboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InboxError::Wire(err) => write!(f, "{}", err),
+            InboxError::UnknownSender => write!(f, "message is not from a seated player"),
+            InboxError::WrongGame => write!(f, "message was signed for another game"),
+            InboxError::Replayed { seat, sequence } => {
+                write!(f, "seat {} replayed message {}", seat, sequence)
+            }
+            InboxError::OutOfOrder {
+                seat,
+                expected,
+                got,
+            } => write!(
+                f,
+                "seat {} sent message {} but {} was expected",
+                seat, got, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InboxError {}
+
+impl From<WireError> for InboxError {
+    fn from(err: WireError) -> Self {
+        InboxError::Wire(err)
+    }
+}
+
+// Admits player messages for one game. A message is only accepted if it is signed by a
+// seated player, for this game, and carries exactly that player's next sequence number, so
+// nothing can be forged, reordered or replayed.
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    game_id: GameId,
+    players: Vec<PublicKey>,
+    next_sequences: Vec<u64>,
+}
+
+impl Inbox {
+    pub fn new(game_id: GameId, players: Vec<PublicKey>) -> Self {
+        let next_sequences = vec![0; players.len()];
+        Inbox {
+            game_id,
+            players,
+            next_sequences,
+        }
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn next_sequence(&self, seat: usize) -> Option<u64> {
+        self.next_sequences.get(seat).copied()
+    }
+
+    // Checks a signed message and returns the sender's seat with its contents.
+    pub fn receive<'a>(
+        &mut self,
+        signed: &'a SignedMessage,
+    ) -> Result<(usize, &'a Message), InboxError> {
+        let seat = self
+            .players
+            .iter()
+            .position(|public| public == &signed.public)
+            .ok_or(InboxError::UnknownSender)?;
+        signed.verify()?;
+        if signed.game_id != self.game_id {
+            return Err(InboxError::WrongGame);
+        }
+        let expected = self.next_sequences[seat];
+        match signed.sequence.cmp(&expected) {
+            Ordering::Less => Err(InboxError::Replayed {
+                seat,
+                sequence: signed.sequence,
+            }),
+            Ordering::Greater => Err(InboxError::OutOfOrder {
+                seat,
+                expected,
+                got: signed.sequence,
+            }),
+            Ordering::Equal => {
+                self.next_sequences[seat] += 1;
+                Ok((seat, &signed.message))
+            }
+        }
+    }
+
+    // Like `receive`, for a message straight off the wire. Anything that is not a signed
+    // message, such as a bare `Message`, is rejected.
+    pub fn receive_json(&mut self, json: &str) -> Result<(usize, Message), InboxError> {
+        let signed = SignedMessage::from_json(json)?;
+        self.receive(&signed)
+            .map(|(seat, message)| (seat, message.clone()))
+    }
+
+    pub fn receive_bytes(&mut self, bytes: &[u8]) -> Result<(usize, Message), InboxError> {
+        let signed = SignedMessage::from_bytes(bytes)?;
+        self.receive(&signed)
+            .map(|(seat, message)| (seat, message.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::Action;
+    use crate::player::Player;
+    use crate::wire::to_json;
+
+    const GAME: GameId = [1; 32];
+
+    fn action(action: Action) -> Message {
+        Message::Action { action }
+    }
+
+    fn table() -> (Vec<Player>, Inbox) {
+        let players = vec![Player::new(), Player::new()];
+        let inbox = Inbox::new(GAME, players.iter().map(Player::public_key).collect());
+        (players, inbox)
+    }
+
+    #[test]
+    fn test_accepts_messages_in_order() {
+        let (mut players, mut inbox) = table();
+        let first = players[1].sign_message(&GAME, action(Action::Call));
+        let second = players[0].sign_message(&GAME, action(Action::Check));
+        let third = players[1].sign_message(&GAME, action(Action::Bet(20)));
+        assert_eq!(inbox.receive(&first), Ok((1, &action(Action::Call))));
+        assert_eq!(inbox.receive(&second), Ok((0, &action(Action::Check))));
+        assert_eq!(
+            inbox.receive_bytes(&third.to_bytes()),
+            Ok((1, action(Action::Bet(20))))
+        );
+        assert_eq!(inbox.next_sequence(0), Some(1));
+        assert_eq!(inbox.next_sequence(1), Some(2));
+    }
+
+    #[test]
+    fn test_rejects_replays_and_gaps() {
+        let (mut players, mut inbox) = table();
+        let first = players[0].sign_message(&GAME, action(Action::Call));
+        let second = players[0].sign_message(&GAME, action(Action::Fold));
+        assert_eq!(
+            inbox.receive(&second),
+            Err(InboxError::OutOfOrder {
+                seat: 0,
+                expected: 0,
+                got: 1
+            })
+        );
+        assert!(inbox.receive(&first).is_ok());
+        assert_eq!(
+            inbox.receive_json(&first.to_json()),
+            Err(InboxError::Replayed {
+                seat: 0,
+                sequence: 0
+            })
+        );
+        assert!(inbox.receive(&second).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_forged_and_foreign_messages() {
+        let (mut players, mut inbox) = table();
+        let mut forged = players[0].sign_message(&GAME, action(Action::Call));
+        forged.message = action(Action::AllIn);
+        assert_eq!(
+            inbox.receive(&forged),
+            Err(InboxError::Wire(WireError::BadSignature))
+        );
+
+        let mut stolen = players[1].sign_message(&GAME, action(Action::Fold));
+        stolen.public = players[0].public_key();
+        assert_eq!(
+            inbox.receive(&stolen),
+            Err(InboxError::Wire(WireError::BadSignature))
+        );
+
+        let other_game = players[0].sign_message(&[2; 32], action(Action::Call));
+        assert_eq!(inbox.receive(&other_game), Err(InboxError::WrongGame));
+
+        let mut stranger = Player::new();
+        let intruder = stranger.sign_message(&GAME, action(Action::Call));
+        assert_eq!(inbox.receive(&intruder), Err(InboxError::UnknownSender));
+
+        // None of the rejected messages used up a sequence number.
+        assert_eq!(inbox.next_sequence(0), Some(0));
+        assert_eq!(inbox.next_sequence(1), Some(0));
+    }
+
+    #[test]
+    fn test_rejects_unsigned_messages() {
+        let (_, mut inbox) = table();
+        assert!(matches!(
+            inbox.receive_json(&to_json(&action(Action::Call))),
+            Err(InboxError::Wire(WireError::InvalidEncoding(_)))
+        ));
+    }
+}
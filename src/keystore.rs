@@ -0,0 +1,330 @@
+use crate::wire::{from_json, hex_encoded, to_json, WireError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+// Bumped whenever the keystore file layout or its cryptography changes.
+pub const KEYSTORE_VERSION: u16 = 1;
+
+pub type Seed = [u8; 32];
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// Bounds on the Argon2 settings a keystore file may ask for. A file from elsewhere could
+// otherwise make us allocate without limit or spin for hours, or weaken the derivation
+// far below what `KdfParams::random` picks.
+const MIN_M_COST: u32 = Params::DEFAULT_M_COST;
+const MAX_M_COST: u32 = 1 << 21;
+const MIN_T_COST: u32 = Params::DEFAULT_T_COST;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+const MIN_SALT_LEN: usize = 8;
+const MAX_SALT_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    UnsupportedVersion(u16),
+    InvalidParams,
+    WrongPassphrase,
+    KeyMismatch,
+    Encoding(WireError),
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::UnsupportedVersion(version) => {
+                write!(f, "unsupported keystore version {}", version)
+            }
+            KeystoreError::InvalidParams => write!(f, "invalid key derivation parameters"),
+            KeystoreError::WrongPassphrase => {
+                write!(f, "wrong passphrase or corrupted keystore")
+            }
+            KeystoreError::KeyMismatch => {
+                write!(
+                    f,
+                    "decrypted secret key does not match the stored public key"
+                )
+            }
+            KeystoreError::Encoding(err) => write!(f, "{}", err),
+            KeystoreError::Io(kind) => write!(f, "keystore file error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<WireError> for KeystoreError {
+    fn from(err: WireError) -> Self {
+        KeystoreError::Encoding(err)
+    }
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(err: std::io::Error) -> Self {
+        KeystoreError::Io(err.kind())
+    }
+}
+
+// A fresh random seed; keep it secret, it is the player's whole identity.
+pub fn generate_seed() -> Seed {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    seed
+}
+
+// Expands a seed the same way sr25519 wallets do, so the public key matches theirs.
+pub fn keypair_from_seed(seed: &Seed) -> Keypair {
+    MiniSecretKey::from_bytes(seed)
+        .expect("a 32-byte seed is always a valid mini secret key")
+        .expand_to_keypair(ExpansionMode::Ed25519)
+}
+
+// Argon2id settings used to stretch the passphrase into an encryption key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    #[serde(with = "hex_encoded")]
+    pub salt: Vec<u8>,
+}
+
+impl KdfParams {
+    fn random() -> Self {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        KdfParams {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+            salt,
+        }
+    }
+
+    fn check(&self) -> Result<(), KeystoreError> {
+        let in_range = (MIN_M_COST..=MAX_M_COST).contains(&self.m_cost)
+            && (MIN_T_COST..=MAX_T_COST).contains(&self.t_cost)
+            && (1..=MAX_P_COST).contains(&self.p_cost)
+            && (MIN_SALT_LEN..=MAX_SALT_LEN).contains(&self.salt.len());
+        in_range.then_some(()).ok_or(KeystoreError::InvalidParams)
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+        self.check()?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|_| KeystoreError::InvalidParams)?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, key.as_mut())
+            .map_err(|_| KeystoreError::InvalidParams)?;
+        Ok(key)
+    }
+}
+
+// A secret key encrypted under a passphrase, safe to write to disk.
+// The public key is stored in the clear and authenticated with the ciphertext.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u16,
+    #[serde(with = "hex_encoded")]
+    pub public: PublicKey,
+    pub kdf: KdfParams,
+    #[serde(with = "hex_encoded")]
+    pub nonce: Vec<u8>,
+    #[serde(with = "hex_encoded")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &Keypair, passphrase: &str) -> Result<Self, KeystoreError> {
+        let kdf = KdfParams::random();
+        let key = kdf.derive_key(passphrase)?;
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let public = keypair.public;
+        let secret = Zeroizing::new(keypair.secret.to_bytes());
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_ref(),
+                    aad: &public.to_bytes(),
+                },
+            )
+            .expect("encrypting a secret key cannot fail");
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            public,
+            kdf,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.nonce.len() != NONCE_LEN {
+            return Err(KeystoreError::WrongPassphrase);
+        }
+        let key = self.kdf.derive_key(passphrase)?;
+        let secret = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .decrypt(
+                Nonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &self.public.to_bytes(),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| KeystoreError::WrongPassphrase)?;
+        let secret = SecretKey::from_bytes(&secret).map_err(|_| KeystoreError::KeyMismatch)?;
+        let keypair = secret.to_keypair();
+        if keypair.public != self.public {
+            return Err(KeystoreError::KeyMismatch);
+        }
+        Ok(keypair)
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        let keystore: Keystore = from_json(json)?;
+        if keystore.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(keystore.version));
+        }
+        Ok(keystore)
+    }
+
+    // Writes the keystore readable by its owner only.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KeystoreError> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to a new file, so tighten an existing one as well.
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(self.to_json().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let seed = [3u8; 32];
+        assert_eq!(
+            keypair_from_seed(&seed).public,
+            keypair_from_seed(&seed).public
+        );
+        assert_ne!(
+            keypair_from_seed(&seed).public,
+            keypair_from_seed(&[4u8; 32]).public
+        );
+    }
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let keypair = keypair_from_seed(&generate_seed());
+        let keystore = Keystore::encrypt(&keypair, "correct horse").unwrap();
+        let restored = Keystore::from_json(&keystore.to_json()).unwrap();
+        assert_eq!(restored, keystore);
+        let decrypted = restored.decrypt("correct horse").unwrap();
+        assert_eq!(decrypted.public, keypair.public);
+        assert_eq!(decrypted.secret.to_bytes(), keypair.secret.to_bytes());
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let keypair = keypair_from_seed(&[1u8; 32]);
+        let keystore = Keystore::encrypt(&keypair, "right").unwrap();
+        assert!(matches!(
+            keystore.decrypt("wrong"),
+            Err(KeystoreError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn test_swapped_public_key_is_rejected() {
+        let keypair = keypair_from_seed(&[1u8; 32]);
+        let mut keystore = Keystore::encrypt(&keypair, "pass").unwrap();
+        keystore.public = keypair_from_seed(&[2u8; 32]).public;
+        assert!(keystore.decrypt("pass").is_err());
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let keypair = keypair_from_seed(&[1u8; 32]);
+        let mut keystore = Keystore::encrypt(&keypair, "pass").unwrap();
+        keystore.version = KEYSTORE_VERSION + 1;
+        assert!(matches!(
+            Keystore::from_json(&keystore.to_json()),
+            Err(KeystoreError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("vrf-poker-{}.json", hex::encode(generate_seed())));
+        let keypair = keypair_from_seed(&[5u8; 32]);
+        Keystore::encrypt(&keypair, "pass")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let loaded = Keystore::load(&path).unwrap().decrypt("pass").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.public, keypair.public);
+    }
+
+    #[test]
+    fn test_out_of_range_kdf_params_are_rejected() {
+        let keypair = keypair_from_seed(&[1u8; 32]);
+        let keystore = Keystore::encrypt(&keypair, "pass").unwrap();
+        let tweaks: [fn(&mut KdfParams); 5] = [
+            |kdf| kdf.m_cost = u32::MAX,
+            |kdf| kdf.m_cost = 8,
+            |kdf| kdf.t_cost = 1_000_000,
+            |kdf| kdf.p_cost = 0,
+            |kdf| kdf.salt.clear(),
+        ];
+        for tweak in tweaks {
+            let mut tweaked = keystore.clone();
+            tweak(&mut tweaked.kdf);
+            assert_eq!(
+                tweaked.decrypt("pass").err(),
+                Some(KeystoreError::InvalidParams)
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+pub mod beacon;
+pub mod betting;
+pub mod card;
+pub mod commit;
+pub mod deck;
+pub mod engine;
+pub mod error;
+pub mod game;
+pub mod hand;
+pub mod holdem;
+pub mod inbox;
+pub mod keystore;
+pub mod mental;
+pub mod mental_hand;
+pub mod net;
+pub mod peer;
+pub mod player;
+pub mod pot;
+pub mod seed;
+pub mod shuffle;
+pub mod table;
+pub mod threshold;
+pub mod transcript;
+pub mod verify;
+pub mod wire;
+
+pub use beacon::{
+    BeaconError, CommitRevealBeacon, FileBeacon, LocalBeacon, RandomnessBeacon, RoundInput,
+    SeededBeacon,
+};
+pub use betting::{
+    check_blinds, Action, Betting, BettingError, Chips, ParseActionError, SeatState,
+};
+pub use card::{Card, ParseCardError, Rank, Suit};
+pub use commit::{commit, forfeit, CommitError, CommitReveal, Commitment, Contribution};
+pub use deck::{sample_index, Deck, DECK_SIZE};
+pub use engine::{Effect, Event, GameState};
+pub use error::PokerError;
+pub use game::{Draw, Game, Round};
+pub use hand::{best_hand, evaluate, winners, Category, HandRank};
+pub use holdem::{Holdem, Seat, Settlement, Showdown, ShowdownHand, Street};
+pub use inbox::{Inbox, InboxError};
+pub use keystore::{
+    generate_seed, keypair_from_seed, KdfParams, Keystore, KeystoreError, Seed, KEYSTORE_VERSION,
+};
+pub use mental::{
+    joint_key, open_card, Ciphertext, DeckKey, DecryptionShare, EncryptedDeck, KeyAnnouncement,
+    MentalError,
+};
+pub use mental_hand::{MentalHand, Opened};
+pub use net::{Client, Connection, Lobby, NetError, ServerMessage, TableHost, Update};
+pub use peer::{Peer, PeerError, PeerMessage};
+pub use player::Player;
+pub use pot::{build_pots, distribute, Pot};
+pub use seed::JointSeed;
+pub use shuffle::{prove_shuffle, verify_passes, ShuffleError, ShuffleProof};
+pub use table::{Expected, Table, TableConfig};
+pub use threshold::{
+    ChaumPedersenProof, Dealing, ThresholdError, ThresholdKey, ThresholdPublic, ThresholdShare,
+};
+pub use transcript::{
+    random_game_id, CardSlot, DeckContext, DrawContext, GameId, TableId, DRAW_LABEL,
+};
+pub use verify::{verify_claims, verify_draw, verify_each, CardClaim, InvalidClaims};
+pub use wire::{Message, SignedMessage, WireBytes, WireError, WIRE_VERSION};
@@ -1,141 +1,158 @@
-use schnorrkel::{Keypair, MiniSecretKey, PublicKey, Signature, signing_context, vrf::{VRFInOut, VRFProof}};
-use rand::{rngs::OsRng, Rng};
-use std::collections::HashMap;
-
-const CONTEXT: &[u8] = b"example";
-
-#[derive(Debug)]
-struct Player {
-    keypair: Keypair,
-    vrf_output: Option<VRFInOut>,
-    vrf_proof: Option<VRFProof>,
+use pba5_vrf_poker_game_group2::{
+    prove_shuffle, Action, Card, Chips, DeckKey, Effect, Event, Game, GameState, Message,
+    PokerError,
+};
+use rand::rngs::OsRng;
+
+const SMALL_BLIND: Chips = 5;
+const BIG_BLIND: Chips = 10;
+
+fn show(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-impl Player {
-    fn new() -> Self {
-        let keypair = Keypair::generate_with(OsRng);
-        Player {
-            keypair,
-            vrf_output: None,
-            vrf_proof: None,
-        }
-    }
-
-    fn draw_card(&mut self, input: &[u8]) {
-        // References https://docs.rs/schnorrkel/latest/schnorrkel/keys/struct.Keypair.html#method.vrf_sign
-        // The VRF generates an output that is deterministic from the input and the private key, but appears random and is not reversible.
-        // The VRF output and a proof that can be used to verify the correctness of the VRF output without revealing the private key.
-        let (inout, proof, _) = self.keypair.vrf_sign(signing_context(CONTEXT).bytes(input));
-        self.vrf_output = Some(inout);
-        self.vrf_proof = Some(proof);
-    }
-
-    fn reveal_card(&self) -> Option<u8> {
-        self.vrf_output.as_ref().and_then(|output| {
-            let hash: Vec<u8> = output.output.to_bytes().to_vec();
-            if hash.len() < 8 {
-                return None;
+// The engine only reports what happened; printing it is up to us.
+fn print_effects(state: &GameState, effects: &[Effect]) {
+    let name = |seat: usize| &state.players()[seat].0;
+    for effect in effects {
+        match effect {
+            Effect::HandStarted { number, dealer } => {
+                println!("Hand {} ({} deals)", number, name(*dealer))
+            }
+            Effect::Shuffled { seat } => println!("{} shuffled the deck", name(*seat)),
+            Effect::SharesAccepted { .. } | Effect::HoleCardsDealt | Effect::ToAct { .. } => {}
+            Effect::HoleCards { seat, cards } => {
+                println!("{} shows {}", name(*seat), show(cards))
+            }
+            Effect::Acted { seat, action } => println!("{}: {:?}", name(*seat), action),
+            Effect::StreetDealt { street, board } => println!("{:?}: {}", street, show(board)),
+            Effect::HandFinished { settlement, stacks } => {
+                for shown in &settlement.showdown.hands {
+                    println!(
+                        "{} shows {} ({})",
+                        shown.player,
+                        show(&shown.cards),
+                        shown.rank.category
+                    );
+                }
+                for (seat, &won) in settlement.payouts.iter().enumerate() {
+                    if won > 0 {
+                        println!("{} wins {}!", name(seat), won);
+                    }
+                }
+                for (seat, stack) in stacks.iter().enumerate() {
+                    println!("{} has {} chips", name(seat), stack);
+                }
+            }
+            Effect::Forfeited { seats, stacks } => {
+                for &seat in seats {
+                    println!(
+                        "{} timed out or left and forfeited; {} chips left",
+                        name(seat),
+                        stacks[seat]
+                    );
+                }
             }
-            let card_value = u64::from_le_bytes(hash[0..8].try_into().unwrap()) % 52;
-            Some(card_value as u8)
-        })
-    }
-    
-    fn verify_card(&self, input: &[u8]) -> bool {
-        if let (Some(output), Some(proof)) = (&self.vrf_output, &self.vrf_proof) {
-            self.keypair
-                .public
-                .vrf_verify(signing_context(CONTEXT).bytes(input), &output.to_preout(), proof)
-                .is_ok()
-        } else {
-            false
         }
     }
 }
 
-fn main() {
-    let mut players: HashMap<&str, Player> = HashMap::new();
-    players.insert("Alice", Player::new());
-    players.insert("Bob", Player::new());
-
-
-    for round in 1..=10 {
-        println!("Round {}", round);
-    
-        // Commit-reveal phase
-        let mut rng = rand::thread_rng();
-        let commit_input_vec: Vec<u8> = (0..10).map(|_| rng.gen()).collect();
-        let commit_input: &[u8] = commit_input_vec.as_slice();
-        
-        // Players draw their cards
-        for player in players.values_mut() {
-            player.draw_card(commit_input);
-        }
-    
-        // Reveal phase
-        for (name, player) in &players {
-            if let Some(card) = player.reveal_card() {
-                println!("{}'s card: {}", name, card);
+fn main() -> Result<(), PokerError> {
+    let mut game = Game::new();
+    game.add_player("Alice");
+    game.add_player("Bob");
+    let game_id = *game.id();
+    let mut inbox = game.inbox();
+    let players: Vec<_> = game
+        .players()
+        .map(|(name, player)| (name.to_string(), player.public_key()))
+        .collect();
+    // Each player's deck key for this table. Only its owner ever uses it.
+    let mut deck_keys: Vec<DeckKey> = players.iter().map(|_| DeckKey::generate(OsRng)).collect();
+    let announced: Vec<_> = deck_keys
+        .iter()
+        .zip(&players)
+        .map(|(key, (_, public))| key.announce(public))
+        .collect();
+    let mut state = GameState::new(
+        game_id,
+        game.table(),
+        players,
+        &announced,
+        vec![1000, 1000],
+        (SMALL_BLIND, BIG_BLIND),
+    )?;
+    let names: Vec<String> = game.players().map(|(name, _)| name.to_string()).collect();
+
+    for _ in 0..10 {
+        // Commit-reveal among the players, unless the game was given another beacon. The
+        // input orders the starting deck that everyone then shuffles.
+        let input = game.round_input(state.hand_number() + 1)?;
+        let effects = match state.apply(Event::StartHand {
+            input: input.to_vec(),
+        }) {
+            Err(PokerError::GameOver) => break,
+            result => result?,
+        };
+        print_effects(&state, &effects);
+
+        loop {
+            // Everyone shuffles the deck in turn; the engine checks each shuffle proof.
+            let effects = if let Some(seat) = state.shuffler() {
+                let (deck, proof) =
+                    prove_shuffle(state.deck().expect("hand in progress"), &mut OsRng);
+                state.apply(Event::Shuffle {
+                    seat,
+                    deck: deck.cards().to_vec(),
+                    proof: Box::new(proof),
+                })?
+            } else if let Some(seat) =
+                (0..names.len()).find(|&seat| !state.owed_shares(seat).is_empty())
+            {
+                // Cards open once every player has sent a decryption share for them.
+                let key = &mut deck_keys[seat];
+                state.prepare_key(key);
+                let shares = state
+                    .owed_shares(seat)
+                    .into_iter()
+                    .map(|position| key.decryption_share(position).expect("card in the deck"))
+                    .collect();
+                state.apply(Event::Shares { seat, shares })?
+            } else if let Some(seat) = state.to_act() {
+                // Everyone simply checks or calls. Actions are signed by the player and only
+                // applied once the inbox has accepted them.
+                let betting = state.betting().expect("hand in progress");
+                let action = if betting.amount_to_call(seat) == 0 {
+                    Action::Check
+                } else {
+                    Action::Call
+                };
+                let player = game.player_mut(&names[seat]).expect("seated player");
+                let signed = player.sign_message(&game_id, Message::Action { action });
+                let (seat, Message::Action { action }) = inbox.receive(&signed)? else {
+                    unreachable!("we signed an action");
+                };
+                state.apply(Event::Act {
+                    seat,
+                    action: *action,
+                })?
             } else {
-                println!("{} has not drawn a card.", name);
+                break;
+            };
+            print_effects(&state, &effects);
+
+            // Each player opens their own hole cards; nobody else can.
+            if effects.contains(&Effect::HoleCardsDealt) {
+                for (seat, key) in deck_keys.iter_mut().enumerate() {
+                    let cards = state.open_hole_cards(seat, key)?;
+                    println!("{} looks at {}", names[seat], show(&cards));
+                }
             }
         }
-    
-        // Verify the cards
-        for (name, player) in &players {
-            let is_valid = player.verify_card(commit_input);
-            println!("{}'s card is valid: {}", name, is_valid);
-        }
-
-        let winner = players.iter().max_by_key(|&(_, player)| player.reveal_card().unwrap_or(0));
-        if let Some((name, _)) = winner {
-            println!("{} wins!", name);
-        }
     }
+    Ok(())
 }
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_new_player() {
-        let player = Player::new();
-        assert!(player.vrf_output.is_none());
-        assert!(player.vrf_proof.is_none());
-    }
-
-    #[test]
-    fn test_draw_card() {
-        let mut player = Player::new();
-        player.draw_card(b"test");
-        assert!(player.vrf_output.is_some());
-        assert!(player.vrf_proof.is_some());
-    }
-
-    #[test]
-    fn test_reveal_card() {
-        let mut player = Player::new();
-        player.draw_card(b"test");
-        let card = player.reveal_card();
-        assert!(card.is_some());
-        assert!(card.unwrap() < 52);
-    }
-
-    #[test]
-    fn test_verify_card() {
-        let mut player = Player::new();
-        player.draw_card(b"test");
-        let is_valid = player.verify_card(b"test");
-        assert!(is_valid);
-    }
-
-    #[test]
-    fn test_verify_card_with_wrong_input() {
-        let mut player = Player::new();
-        player.draw_card(b"test");
-        let is_valid = player.verify_card(b"wrong");
-        assert!(!is_valid);
-    }
-}
\ No newline at end of file
@@ -0,0 +1,636 @@
+use crate::card::Card;
+use crate::deck::{uniform_index, DECK_SIZE};
+use crate::shuffle::{verify_passes, ShuffleError, ShuffleProof};
+use crate::transcript::DeckContext;
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar, traits::Identity};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
+use schnorrkel::{
+    points::RistrettoBoth,
+    signing_context,
+    vrf::{VRFInOut, VRFProof},
+    Keypair, PublicKey, Signature,
+};
+use std::fmt;
+
+pub const DECRYPTION_SHARE_LABEL: &[u8] = b"vrf-poker-decryption-share";
+pub const DECK_KEY_LABEL: &[u8] = b"vrf-poker-deck-key";
+pub const DECK_ORDER_LABEL: &[u8] = b"vrf-poker-deck-order";
+
+// Cards are encoded as the points G, 2G, ..., 52G so they can be recognised after
+// decryption.
+pub fn card_point(card: Card) -> RistrettoPoint {
+    RistrettoPoint::mul_base(&Scalar::from(card.index() as u64 + 1))
+}
+
+pub fn decode_card(point: &RistrettoPoint) -> Option<Card> {
+    (0..DECK_SIZE as u8)
+        .filter_map(Card::from_index)
+        .find(|&card| card_point(card) == *point)
+}
+
+// A player's deck key with a proof that they know its secret: a Schnorr signature under
+// the deck key over the player's identity key. Without the proof a player could announce
+// their own key minus everyone else's and choose the joint key on their own, and tying it
+// to the identity key stops anyone from passing off another player's key as theirs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyAnnouncement {
+    pub key: PublicKey,
+    pub proof: Signature,
+}
+
+impl KeyAnnouncement {
+    pub fn verify(&self, owner: &PublicKey) -> bool {
+        self.key
+            .verify(
+                signing_context(DECK_KEY_LABEL).bytes(&owner.to_bytes()),
+                &self.proof,
+            )
+            .is_ok()
+    }
+}
+
+// The table's ElGamal key: the sum of every seat's deck key, each announced by the seat's
+// owner in `owners`. Decrypting anything encrypted to it takes a share from every seat.
+pub fn joint_key(
+    owners: &[PublicKey],
+    announcements: &[KeyAnnouncement],
+) -> Result<RistrettoPoint, MentalError> {
+    if owners.len() != announcements.len() {
+        return Err(MentalError::InvalidKey(
+            owners.len().min(announcements.len()),
+        ));
+    }
+    for (seat, (owner, announcement)) in owners.iter().zip(announcements).enumerate() {
+        if !announcement.verify(owner) {
+            return Err(MentalError::InvalidKey(seat));
+        }
+    }
+    Ok(announcements
+        .iter()
+        .map(|announcement| *announcement.key.as_point())
+        .sum())
+}
+
+// An ElGamal ciphertext (rG, M + rH) under the joint key H.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub c1: RistrettoPoint,
+    pub c2: RistrettoPoint,
+}
+
+impl Ciphertext {
+    // A card in the clear, with no randomness yet.
+    pub fn open(card: Card) -> Self {
+        Ciphertext {
+            c1: RistrettoPoint::identity(),
+            c2: card_point(card),
+        }
+    }
+
+    // Adds fresh randomness without changing the card underneath. Anyone can do this,
+    // and nobody can link the result to the original without the secret keys.
+    pub fn rerandomize(&self, joint_key: &RistrettoPoint, r: &Scalar) -> Self {
+        Ciphertext {
+            c1: self.c1 + RistrettoPoint::mul_base(r),
+            c2: self.c2 + joint_key * r,
+        }
+    }
+
+    // Recovers the card once every player's decryption share is known.
+    pub fn decrypt(&self, shares: &[RistrettoPoint]) -> Option<Card> {
+        let mask: RistrettoPoint = shares.iter().sum();
+        decode_card(&(self.c2 - mask))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MentalError {
+    // Seats, by position in the player list, whose deck key or share is bad or missing.
+    InvalidKey(usize),
+    InvalidShare(usize),
+    MissingShare(usize),
+    // The shares verified but the result is not a card, so the deck was tampered with.
+    NotACard,
+}
+
+impl fmt::Display for MentalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MentalError::InvalidKey(seat) => write!(f, "invalid deck key from seat {}", seat),
+            MentalError::InvalidShare(seat) => {
+                write!(f, "invalid decryption share from seat {}", seat)
+            }
+            MentalError::MissingShare(seat) => {
+                write!(f, "missing decryption share from seat {}", seat)
+            }
+            MentalError::NotACard => write!(f, "ciphertext does not decrypt to a card"),
+        }
+    }
+}
+
+impl std::error::Error for MentalError {}
+
+// A player's part of the decryption of one card, x·c1, with a proof that it used the same
+// secret key as their public key. The proof is schnorrkel's DLEQ proof, the same one that
+// backs VRF outputs, taken over the ciphertext point instead of a hashed input, and it
+// only verifies for the hand the deck was dealt for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptionShare {
+    pub public: PublicKey,
+    pub share: RistrettoPoint,
+    pub proof: VRFProof,
+}
+
+fn share_transcript(context: &DeckContext, ciphertext: &Ciphertext) -> Transcript {
+    let mut transcript = Transcript::new(DECRYPTION_SHARE_LABEL);
+    context.append_to(&mut transcript);
+    transcript.append_message(b"c1", ciphertext.c1.compress().as_bytes());
+    transcript.append_message(b"c2", ciphertext.c2.compress().as_bytes());
+    transcript
+}
+
+impl DecryptionShare {
+    fn new(keypair: &Keypair, context: &DeckContext, ciphertext: &Ciphertext) -> Self {
+        let inout = keypair
+            .secret
+            .vrf_create_from_point(RistrettoBoth::from_point(ciphertext.c1));
+        let (proof, _) = keypair.dleq_proove(share_transcript(context, ciphertext), &inout, false);
+        DecryptionShare {
+            public: keypair.public,
+            share: *inout.output.as_point(),
+            proof,
+        }
+    }
+
+    pub fn verify(&self, context: &DeckContext, ciphertext: &Ciphertext) -> bool {
+        let inout = VRFInOut {
+            input: RistrettoBoth::from_point(ciphertext.c1),
+            output: RistrettoBoth::from_point(self.share),
+        };
+        self.public
+            .dleq_verify(
+                share_transcript(context, ciphertext),
+                &inout,
+                &self.proof,
+                false,
+            )
+            .is_ok()
+    }
+}
+
+// Opens a card of the deck for `context` given a verified share from every player in
+// `players`.
+pub fn open_card(
+    context: &DeckContext,
+    ciphertext: &Ciphertext,
+    players: &[PublicKey],
+    shares: &[DecryptionShare],
+) -> Result<Card, MentalError> {
+    let mut points = Vec::with_capacity(players.len());
+    for (seat, public) in players.iter().enumerate() {
+        let share = shares
+            .iter()
+            .find(|share| &share.public == public)
+            .ok_or(MentalError::MissingShare(seat))?;
+        if !share.verify(context, ciphertext) {
+            return Err(MentalError::InvalidShare(seat));
+        }
+        points.push(share.share);
+    }
+    ciphertext.decrypt(&points).ok_or(MentalError::NotACard)
+}
+
+// A player's ElGamal key for one table, generated fresh so that it has nothing to do with
+// their identity key. It only hands out decryption shares for cards of the deck it was
+// last given, and only once that deck is a verified shuffle of the open deck: whatever
+// anyone asks it to decrypt is a card, so a share can never leak anything but a card.
+#[derive(Debug)]
+pub struct DeckKey {
+    keypair: Keypair,
+    deck: Option<EncryptedDeck>,
+}
+
+impl DeckKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: R) -> Self {
+        DeckKey {
+            keypair: Keypair::generate_with(rng),
+            deck: None,
+        }
+    }
+
+    pub fn public(&self) -> PublicKey {
+        self.keypair.public
+    }
+
+    pub fn deck(&self) -> Option<&EncryptedDeck> {
+        self.deck.as_ref()
+    }
+
+    // Publishes this key for the seat whose identity key is `owner`.
+    pub fn announce(&self, owner: &PublicKey) -> KeyAnnouncement {
+        KeyAnnouncement {
+            key: self.keypair.public,
+            proof: self
+                .keypair
+                .sign(signing_context(DECK_KEY_LABEL).bytes(&owner.to_bytes())),
+        }
+    }
+
+    // Checks the shuffle passes on top of the open deck for `context` under `joint_key`
+    // and, if they all hold up, switches to the resulting deck. The previous deck is
+    // forgotten either way.
+    pub fn use_deck(
+        &mut self,
+        context: DeckContext,
+        joint_key: RistrettoPoint,
+        passes: &[(EncryptedDeck, ShuffleProof)],
+    ) -> Result<(), ShuffleError> {
+        self.deck = None;
+        let deck = verify_passes(&EncryptedDeck::new(context, joint_key), passes)?.clone();
+        self.deck = Some(deck);
+        Ok(())
+    }
+
+    // Switches to a deck whose passes the caller has already verified, such as the deck
+    // of a `GameState` hand.
+    pub(crate) fn use_verified_deck(&mut self, deck: EncryptedDeck) {
+        self.deck = Some(deck);
+    }
+
+    // Our share of the card at `position` in the current deck.
+    pub fn decryption_share(&self, position: usize) -> Option<DecryptionShare> {
+        let deck = self.deck.as_ref()?;
+        let ciphertext = deck.cards.get(position)?;
+        Some(DecryptionShare::new(
+            &self.keypair,
+            &deck.context,
+            ciphertext,
+        ))
+    }
+
+    // Privately opens one of our own cards from everyone else's verified shares. Our own
+    // share never leaves this function, so nobody else learns the card until we publish it.
+    pub fn open_own_card(
+        &self,
+        position: usize,
+        players: &[PublicKey],
+        shares: &[DecryptionShare],
+    ) -> Result<Card, MentalError> {
+        let deck = self.deck.as_ref().ok_or(MentalError::NotACard)?;
+        let ciphertext = deck.cards.get(position).ok_or(MentalError::NotACard)?;
+        let mut shares = shares.to_vec();
+        shares.retain(|share| share.public != self.keypair.public);
+        shares.push(DecryptionShare::new(
+            &self.keypair,
+            &deck.context,
+            ciphertext,
+        ));
+        open_card(&deck.context, ciphertext, players, &shares)
+    }
+}
+
+// A deck encrypted under the table's joint key for one hand. Every player in turn shuffles
+// and re-randomizes it, so once everyone has taken a pass nobody knows where any card is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedDeck {
+    context: DeckContext,
+    joint_key: RistrettoPoint,
+    cards: Vec<Ciphertext>,
+}
+
+impl EncryptedDeck {
+    // The starting deck for the hand in `context`: all 52 cards in the clear, in an order
+    // drawn from the hand's round input. Every seat's pass shuffles it further, so the deal
+    // depends on the round input as well as on every pass.
+    pub fn new(context: DeckContext, joint_key: RistrettoPoint) -> Self {
+        let mut transcript = Transcript::new(DECK_ORDER_LABEL);
+        context.append_to(&mut transcript);
+        let mut seed = [0u8; 32];
+        transcript.challenge_bytes(b"seed", &mut seed);
+        let cards = random_permutation(DECK_SIZE, &mut ChaChaRng::from_seed(seed))
+            .into_iter()
+            .filter_map(|index| Card::from_index(index as u8))
+            .map(Ciphertext::open)
+            .collect();
+        EncryptedDeck {
+            context,
+            joint_key,
+            cards,
+        }
+    }
+
+    // A deck received from another player, e.g. the output of their shuffle pass.
+    pub fn from_cards(
+        context: DeckContext,
+        joint_key: RistrettoPoint,
+        cards: Vec<Ciphertext>,
+    ) -> Self {
+        EncryptedDeck {
+            context,
+            joint_key,
+            cards,
+        }
+    }
+
+    pub fn context(&self) -> &DeckContext {
+        &self.context
+    }
+
+    pub fn joint_key(&self) -> &RistrettoPoint {
+        &self.joint_key
+    }
+
+    pub fn cards(&self) -> &[Ciphertext] {
+        &self.cards
+    }
+
+    // One shuffle pass without a proof. See `shuffle::prove_shuffle` for a pass the rest
+    // of the table can check.
+    pub fn shuffle<R: RngCore + CryptoRng>(&self, rng: &mut R) -> EncryptedDeck {
+        let permutation = random_permutation(self.cards.len(), rng);
+        let randomness: Vec<Scalar> = (0..self.cards.len()).map(|_| Scalar::random(rng)).collect();
+        self.permute(&permutation, &randomness)
+    }
+
+    // Output position i holds input card `permutation[i]` re-randomized with
+    // `randomness[i]`.
+    pub(crate) fn permute(&self, permutation: &[usize], randomness: &[Scalar]) -> EncryptedDeck {
+        let cards = permutation
+            .iter()
+            .zip(randomness)
+            .map(|(&i, r)| self.cards[i].rerandomize(&self.joint_key, r))
+            .collect();
+        EncryptedDeck {
+            context: self.context.clone(),
+            joint_key: self.joint_key,
+            cards,
+        }
+    }
+}
+
+pub(crate) fn random_permutation<R: RngCore>(n: usize, rng: &mut R) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        let j = uniform_index(rng, i + 1);
+        permutation.swap(i, j);
+    }
+    permutation
+}
+
+// Deck positions of a seat's hole cards when dealing one card around the table at a time.
+pub fn hole_card_positions(seat: usize, players: usize) -> [usize; 2] {
+    [seat, seat + players]
+}
+
+// Deck position of the n-th community card, after every player's hole cards.
+pub fn board_position(n: usize, players: usize) -> usize {
+    2 * players + n
+}
+
+// The joint key of a table where each deck key was announced by a fresh identity.
+#[cfg(test)]
+pub(crate) fn announced(keys: &[DeckKey]) -> RistrettoPoint {
+    let owners: Vec<PublicKey> = keys
+        .iter()
+        .map(|_| Keypair::generate_with(rand::rngs::OsRng).public)
+        .collect();
+    let announcements: Vec<KeyAnnouncement> = keys
+        .iter()
+        .zip(&owners)
+        .map(|(key, owner)| key.announce(owner))
+        .collect();
+    joint_key(&owners, &announcements).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shuffle::prove_shuffle;
+    use crate::transcript::test_deck_context;
+    use rand::rngs::OsRng;
+
+    fn keys(n: usize) -> Vec<DeckKey> {
+        (0..n).map(|_| DeckKey::generate(OsRng)).collect()
+    }
+
+    fn publics(keys: &[DeckKey]) -> Vec<PublicKey> {
+        keys.iter().map(DeckKey::public).collect()
+    }
+
+    // Everyone takes a proven shuffle pass, then every key switches to the result.
+    fn shuffled(keys: &mut [DeckKey]) -> EncryptedDeck {
+        let joint = announced(keys);
+        let mut deck = EncryptedDeck::new(test_deck_context(), joint);
+        let mut passes = Vec::new();
+        for _ in 0..keys.len() {
+            let pass = prove_shuffle(&deck, &mut OsRng);
+            deck = pass.0.clone();
+            passes.push(pass);
+        }
+        for key in keys.iter_mut() {
+            key.use_deck(test_deck_context(), joint, &passes).unwrap();
+        }
+        deck
+    }
+
+    fn shares(keys: &[DeckKey], position: usize) -> Vec<DecryptionShare> {
+        keys.iter()
+            .map(|key| key.decryption_share(position).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_card_encoding() {
+        for index in 0..DECK_SIZE as u8 {
+            let card = Card::from_index(index).unwrap();
+            assert_eq!(decode_card(&card_point(card)), Some(card));
+        }
+        assert_eq!(decode_card(&RistrettoPoint::identity()), None);
+    }
+
+    #[test]
+    fn test_starting_deck_follows_the_round_input() {
+        let joint = announced(&keys(2));
+        let deck = EncryptedDeck::new(test_deck_context(), joint);
+        assert_eq!(deck, EncryptedDeck::new(test_deck_context(), joint));
+
+        let mut cards: Vec<Card> = deck
+            .cards()
+            .iter()
+            .map(|card| decode_card(&card.c2).unwrap())
+            .collect();
+        cards.sort();
+        let mut expected: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+        expected.sort();
+        assert_eq!(cards, expected);
+
+        let other = DeckContext {
+            input: b"other".to_vec(),
+            ..test_deck_context()
+        };
+        assert_ne!(EncryptedDeck::new(other, joint).cards(), deck.cards());
+    }
+
+    #[test]
+    fn test_shuffled_deck_decrypts_to_all_cards() {
+        let mut keys = keys(3);
+        let players = publics(&keys);
+        let deck = shuffled(&mut keys);
+        assert_ne!(
+            deck.cards()[0],
+            Ciphertext::open(Card::from_index(0).unwrap())
+        );
+
+        let mut cards: Vec<Card> = deck
+            .cards()
+            .iter()
+            .enumerate()
+            .map(|(position, ciphertext)| {
+                open_card(
+                    deck.context(),
+                    ciphertext,
+                    &players,
+                    &shares(&keys, position),
+                )
+                .unwrap()
+            })
+            .collect();
+        cards.sort();
+        let mut expected: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+        expected.sort();
+        assert_eq!(cards, expected);
+    }
+
+    #[test]
+    fn test_partial_shares_do_not_reveal_card() {
+        let mut keys = keys(3);
+        let deck = shuffled(&mut keys);
+        let points: Vec<RistrettoPoint> = shares(&keys[1..], 0)
+            .iter()
+            .map(|share| share.share)
+            .collect();
+        assert_eq!(deck.cards()[0].decrypt(&points), None);
+    }
+
+    #[test]
+    fn test_private_hole_cards_and_showdown() {
+        let mut keys = keys(3);
+        let players = publics(&keys);
+        let deck = shuffled(&mut keys);
+        let seat = 1;
+
+        for position in hole_card_positions(seat, players.len()) {
+            let ciphertext = &deck.cards()[position];
+            // Everyone else sends the owner their share.
+            let mut others = shares(&keys, position);
+            let own = others.remove(seat);
+            assert_eq!(
+                open_card(deck.context(), ciphertext, &players, &others),
+                Err(MentalError::MissingShare(seat))
+            );
+            let card = keys[seat]
+                .open_own_card(position, &players, &others)
+                .unwrap();
+
+            // At showdown the owner publishes their share and anyone can check the card.
+            others.push(own);
+            assert_eq!(
+                open_card(deck.context(), ciphertext, &players, &others),
+                Ok(card)
+            );
+        }
+    }
+
+    #[test]
+    fn test_forged_share_is_rejected() {
+        let mut keys = keys(2);
+        let players = publics(&keys);
+        let deck = shuffled(&mut keys);
+        let ciphertext = &deck.cards()[0];
+
+        let honest = keys[0].decryption_share(0).unwrap();
+        let mut forged = keys[1].decryption_share(0).unwrap();
+        forged.share += RistrettoPoint::mul_base(&Scalar::ONE);
+        let context = deck.context();
+        assert!(honest.verify(context, ciphertext));
+        assert!(!forged.verify(context, ciphertext));
+        assert!(!honest.verify(context, &deck.cards()[1]));
+        // The same share is no good for the same card in another hand.
+        let next_hand = DeckContext {
+            hand: context.hand + 1,
+            ..context.clone()
+        };
+        assert!(!honest.verify(&next_hand, ciphertext));
+        assert_eq!(
+            open_card(deck.context(), ciphertext, &players, &[honest, forged]),
+            Err(MentalError::InvalidShare(1))
+        );
+    }
+
+    #[test]
+    fn test_key_only_decrypts_the_current_deck() {
+        let mut keys = keys(2);
+        assert_eq!(keys[0].decryption_share(0), None);
+        let deck = shuffled(&mut keys);
+        assert_eq!(keys[0].decryption_share(DECK_SIZE), None);
+
+        // A "deck" holding a ciphertext of someone else's choosing is not a shuffle of the
+        // open deck, so the key refuses it and forgets the deck it had.
+        let joint = *deck.joint_key();
+        let mut cards = deck.cards().to_vec();
+        cards[0].c1 = RistrettoPoint::mul_base(&Scalar::random(&mut OsRng));
+        let open = EncryptedDeck::new(test_deck_context(), joint);
+        let (_, proof) = prove_shuffle(&open, &mut OsRng);
+        let forged = EncryptedDeck::from_cards(test_deck_context(), joint, cards);
+        assert_eq!(
+            keys[0].use_deck(test_deck_context(), joint, &[(forged, proof)]),
+            Err(ShuffleError::InvalidPass(0))
+        );
+        assert_eq!(keys[0].deck(), None);
+        assert_eq!(keys[0].decryption_share(0), None);
+    }
+
+    #[test]
+    fn test_deck_keys_need_a_proof_of_possession() {
+        let owners: Vec<Keypair> = (0..2).map(|_| Keypair::generate_with(OsRng)).collect();
+        let owners: Vec<PublicKey> = owners.iter().map(|keypair| keypair.public).collect();
+        let keys = keys(2);
+        let honest = keys[0].announce(&owners[0]);
+        let mallory = keys[1].announce(&owners[1]);
+        assert!(joint_key(&owners, &[honest.clone(), mallory.clone()]).is_ok());
+
+        // Mallory cannot announce her key minus Alice's, which would make the joint key
+        // hers alone, because she does not know the secret behind it.
+        let rogue = KeyAnnouncement {
+            key: PublicKey::from_point(mallory.key.as_point() - honest.key.as_point()),
+            proof: mallory.proof,
+        };
+        assert_eq!(
+            joint_key(&owners, &[honest.clone(), rogue]),
+            Err(MentalError::InvalidKey(1))
+        );
+        // Nor can she pass off Alice's announcement as her own.
+        assert_eq!(
+            joint_key(&owners, &[honest.clone(), honest.clone()]),
+            Err(MentalError::InvalidKey(1))
+        );
+        assert_eq!(
+            joint_key(&owners, &[honest]),
+            Err(MentalError::InvalidKey(1))
+        );
+    }
+
+    #[test]
+    fn test_positions_do_not_overlap() {
+        let players = 4;
+        let mut positions: Vec<usize> = (0..players)
+            .flat_map(|seat| hole_card_positions(seat, players))
+            .chain((0..5).map(|n| board_position(n, players)))
+            .collect();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions, (0..2 * players + 5).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::betting::Betting;
+use crate::card::Card;
+use crate::error::PokerError;
+use crate::hand::{best_hand, winners, HandRank};
+use crate::holdem::{Settlement, Showdown, ShowdownHand, Street};
+use crate::mental::{
+    board_position, hole_card_positions, open_card, DecryptionShare, EncryptedDeck, MentalError,
+};
+use crate::pot::{build_pots, distribute};
+use schnorrkel::PublicKey;
+
+// Which cards the table is collecting decryption shares for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opening {
+    // Every seat's shares for everyone else's hole cards, so each owner can open their own.
+    Hole,
+    // Every seat's shares for the community cards of the next street.
+    Board(Street),
+    // Each seat still in the hand shares its own hole cards.
+    Showdown,
+}
+
+// Deck positions of the community cards dealt on `street`.
+fn street_positions(street: Street, players: usize) -> Vec<usize> {
+    let cards = match street {
+        Street::Preflop => 0..0,
+        Street::Flop => 0..3,
+        Street::Turn => 3..4,
+        Street::River => 4..5,
+    };
+    cards.map(|card| board_position(card, players)).collect()
+}
+
+// Cards that became known when the last share of an opening came in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Opened {
+    // Every seat can now open its own hole cards, and nobody else's.
+    Hole,
+    Street(Street),
+    // The hole cards of every seat that was still in the hand, by seat.
+    Showdown(Vec<(usize, Vec<Card>)>),
+}
+
+// One hand of Texas Hold'em dealt from a deck every seat has shuffled in turn. A card only
+// opens with a decryption share from every seat, so community cards are opened together
+// and each player's hole cards stay theirs alone until they show them at showdown.
+#[derive(Debug, Clone)]
+pub struct MentalHand {
+    names: Vec<String>,
+    // Each seat's deck key, which its decryption shares must be made with.
+    keys: Vec<PublicKey>,
+    deck: EncryptedDeck,
+    // Verified shares received so far, by deck position.
+    shares: Vec<Vec<DecryptionShare>>,
+    opening: Option<Opening>,
+    // Seats that have sent everything the current opening needs from them.
+    sent: Vec<bool>,
+    // Seats that have to show at showdown.
+    showing: Vec<bool>,
+    board: Vec<Card>,
+    shown: Option<Vec<Option<Vec<Card>>>>,
+}
+
+impl MentalHand {
+    // Starts collecting hole card shares on `deck`, whose shuffles must already be checked.
+    pub fn new(names: Vec<String>, keys: Vec<PublicKey>, deck: EncryptedDeck) -> Self {
+        let n = keys.len();
+        MentalHand {
+            names,
+            keys,
+            shares: vec![Vec::new(); deck.cards().len()],
+            deck,
+            opening: Some(Opening::Hole),
+            sent: vec![false; n],
+            showing: vec![false; n],
+            board: Vec::new(),
+            shown: None,
+        }
+    }
+
+    pub fn deck(&self) -> &EncryptedDeck {
+        &self.deck
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    // Every verified share for the card at `position`.
+    pub fn shares(&self, position: usize) -> &[DecryptionShare] {
+        self.shares.get(position).map_or(&[], Vec::as_slice)
+    }
+
+    // The hole cards each seat showed, once the showdown is over.
+    pub fn shown(&self) -> Option<&[Option<Vec<Card>>]> {
+        self.shown.as_deref()
+    }
+
+    pub fn street(&self) -> Street {
+        match self.board.len() {
+            0 => Street::Preflop,
+            3 => Street::Flop,
+            4 => Street::Turn,
+            _ => Street::River,
+        }
+    }
+
+    // Whether the hand is waiting for decryption shares.
+    pub fn is_opening(&self) -> bool {
+        self.opening.is_some()
+    }
+
+    // The deck positions `seat` has to send shares for right now, in order.
+    pub fn owed(&self, seat: usize) -> Vec<usize> {
+        let n = self.keys.len();
+        if seat >= n || self.sent[seat] {
+            return Vec::new();
+        }
+        match self.opening {
+            None => Vec::new(),
+            Some(Opening::Hole) => (0..n)
+                .filter(|&other| other != seat)
+                .flat_map(|other| hole_card_positions(other, n))
+                .collect(),
+            Some(Opening::Board(street)) => street_positions(street, n),
+            Some(Opening::Showdown) if self.showing[seat] => hole_card_positions(seat, n).to_vec(),
+            Some(Opening::Showdown) => Vec::new(),
+        }
+    }
+
+    // Checks and keeps `seat`'s shares for every position it owes, in `owed` order.
+    pub fn receive(&mut self, seat: usize, shares: &[DecryptionShare]) -> Result<(), PokerError> {
+        let positions = self.owed(seat);
+        if positions.is_empty() {
+            return Err(PokerError::OutOfPhase);
+        }
+        if shares.len() != positions.len() {
+            return Err(PokerError::Mental(MentalError::MissingShare(seat)));
+        }
+        for (&position, share) in positions.iter().zip(shares) {
+            if share.public != self.keys[seat]
+                || !share.verify(self.deck.context(), &self.deck.cards()[position])
+            {
+                return Err(PokerError::Mental(MentalError::InvalidShare(seat)));
+            }
+        }
+        for (position, share) in positions.into_iter().zip(shares) {
+            self.shares[position].push(share.clone());
+        }
+        self.sent[seat] = true;
+        Ok(())
+    }
+
+    // Asks every seat for its shares of the next street. Returns `None` once the river is
+    // out.
+    pub(crate) fn request_street(&mut self) -> Option<Street> {
+        let street = match self.street() {
+            Street::Preflop => Street::Flop,
+            Street::Flop => Street::Turn,
+            Street::Turn => Street::River,
+            Street::River => return None,
+        };
+        self.request(Opening::Board(street));
+        Some(street)
+    }
+
+    // Asks every seat still in the hand to show its hole cards. Returns whether the
+    // showdown still has to happen.
+    pub(crate) fn request_showdown(&mut self, betting: &Betting) -> bool {
+        if self.shown.is_some() {
+            return false;
+        }
+        self.showing = betting.seats().iter().map(|seat| !seat.folded).collect();
+        self.request(Opening::Showdown);
+        true
+    }
+
+    fn request(&mut self, opening: Opening) {
+        self.opening = Some(opening);
+        self.sent = vec![false; self.keys.len()];
+    }
+
+    // Opens the cards once every share is in. Returns `None` while some are missing.
+    pub(crate) fn open(&mut self) -> Result<Option<Opened>, PokerError> {
+        let Some(opening) = self.opening else {
+            return Ok(None);
+        };
+        if (0..self.keys.len()).any(|seat| !self.owed(seat).is_empty()) {
+            return Ok(None);
+        }
+        self.opening = None;
+        let n = self.keys.len();
+        let opened = match opening {
+            Opening::Hole => Opened::Hole,
+            Opening::Board(street) => {
+                for position in street_positions(street, n) {
+                    let card = self.open_at(position)?;
+                    self.board.push(card);
+                }
+                Opened::Street(street)
+            }
+            Opening::Showdown => {
+                let mut shown = vec![None; n];
+                let mut cards = Vec::new();
+                for seat in (0..n).filter(|&seat| self.showing[seat]) {
+                    let hole = hole_card_positions(seat, n)
+                        .into_iter()
+                        .map(|position| self.open_at(position))
+                        .collect::<Result<Vec<Card>, PokerError>>()?;
+                    shown[seat] = Some(hole.clone());
+                    cards.push((seat, hole));
+                }
+                self.shown = Some(shown);
+                Opened::Showdown(cards)
+            }
+        };
+        Ok(Some(opened))
+    }
+
+    fn open_at(&self, position: usize) -> Result<Card, PokerError> {
+        open_card(
+            self.deck.context(),
+            &self.deck.cards()[position],
+            &self.keys,
+            &self.shares[position],
+        )
+        .map_err(PokerError::Mental)
+    }
+
+    // Pays out the pots once betting has finished. Players who folded never show their
+    // cards, and a player left alone in the hand wins without a showdown.
+    pub fn settle(&self, betting: &Betting) -> Result<Settlement, PokerError> {
+        if betting.seats().len() != self.keys.len() {
+            return Err(PokerError::SeatMismatch);
+        }
+        let (showdown, ranks) = match &self.shown {
+            Some(shown) if !betting.is_hand_over() => self.showdown(shown)?,
+            _ => {
+                let winners = betting
+                    .seats()
+                    .iter()
+                    .zip(&self.names)
+                    .filter(|(seat, _)| !seat.folded)
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                let showdown = Showdown {
+                    hands: Vec::new(),
+                    winners,
+                };
+                (showdown, vec![None; self.keys.len()])
+            }
+        };
+        let pots = build_pots(betting.seats());
+        let payouts = distribute(&pots, &ranks, betting.dealer());
+        Ok(Settlement {
+            showdown,
+            pots,
+            payouts,
+        })
+    }
+
+    fn showdown(
+        &self,
+        shown: &[Option<Vec<Card>>],
+    ) -> Result<(Showdown, Vec<Option<HandRank>>), PokerError> {
+        let mut hands = Vec::new();
+        let mut ranks = vec![None; shown.len()];
+        for (seat, hole) in shown.iter().enumerate() {
+            let Some(hole) = hole else {
+                continue;
+            };
+            let cards: Vec<Card> = hole.iter().chain(&self.board).copied().collect();
+            let (rank, cards) = best_hand(&cards)?;
+            ranks[seat] = Some(rank.clone());
+            hands.push(ShowdownHand {
+                player: self.names[seat].clone(),
+                rank,
+                cards,
+            });
+        }
+        let hand_ranks: Vec<HandRank> = hands.iter().map(|hand| hand.rank.clone()).collect();
+        let winners = winners(&hand_ranks)
+            .into_iter()
+            .map(|i| hands[i].player.clone())
+            .collect();
+        Ok((Showdown { hands, winners }, ranks))
+    }
+}
@@ -0,0 +1,920 @@
+use crate::betting::{Action, Chips};
+use crate::card::Card;
+use crate::engine::Effect;
+use crate::error::PokerError;
+use crate::mental::DeckKey;
+use crate::player::Player;
+use crate::table::{Expected, Table, TableConfig};
+use crate::transcript::{GameId, TableId};
+use crate::wire::{from_json, hex_encoded, to_json, Message, SignedMessage, WireError};
+use rand::rngs::OsRng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a new connection gets, all told, to send its join before the host gives up on it.
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// How long the host waits for whoever owes the table a message before timing them out.
+pub const TURN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The longest line we read. A shuffle, the largest message, takes under 32 KB.
+pub const MAX_LINE_BYTES: u64 = 256 << 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetError {
+    Io(std::io::ErrorKind),
+    Wire(WireError),
+    Disconnected,
+    // The other side sent a valid message that makes no sense at this point.
+    UnexpectedMessage,
+    // The host refused our join, with its reason.
+    Rejected(String),
+    // The other side sent more than `MAX_LINE_BYTES` without ending the line.
+    LineTooLong,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(kind) => write!(f, "connection error: {}", kind),
+            NetError::Wire(err) => write!(f, "{}", err),
+            NetError::Disconnected => write!(f, "connection closed"),
+            NetError::UnexpectedMessage => write!(f, "unexpected message"),
+            NetError::Rejected(reason) => write!(f, "rejected: {}", reason),
+            NetError::LineTooLong => write!(f, "message too long"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<std::io::Error> for NetError {
+    fn from(err: std::io::Error) -> Self {
+        NetError::Io(err.kind())
+    }
+}
+
+impl From<WireError> for NetError {
+    fn from(err: WireError) -> Self {
+        NetError::Wire(err)
+    }
+}
+
+// Everything the host tells the players at a table. Players talk back in `SignedMessage`s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerMessage {
+    // Sent on connecting; the player answers with a signed `Message::Join`.
+    Welcome {
+        #[serde(with = "hex_encoded")]
+        game_id: GameId,
+        table_id: TableId,
+        config: TableConfig,
+    },
+    // The table is full. These joins are all a player needs to set up their own `Table`.
+    Started {
+        joins: Vec<SignedMessage>,
+    },
+    // A message the host accepted, in the order the host accepted it.
+    Relayed {
+        message: Box<SignedMessage>,
+    },
+    // Only sent to whoever sent the offending message.
+    Rejected {
+        reason: String,
+    },
+    // These seats missed their turn deadline or left, and were dealt with by
+    // `Table::time_out`.
+    TimedOut {
+        seats: Vec<usize>,
+    },
+    Finished {
+        stacks: Vec<Chips>,
+    },
+}
+
+// A TCP connection carrying one JSON message per line.
+#[derive(Debug)]
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Result<Self, NetError> {
+        let writer = stream.try_clone()?;
+        Ok(Connection {
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, NetError> {
+        Self::new(TcpStream::connect(addr)?)
+    }
+
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), NetError> {
+        Ok(self.writer.set_read_timeout(timeout)?)
+    }
+
+    pub fn send<T: Serialize>(&mut self, value: &T) -> Result<(), NetError> {
+        send_line(&mut self.writer, value)
+    }
+
+    pub fn receive<T: DeserializeOwned>(&mut self) -> Result<T, NetError> {
+        let mut line = String::new();
+        let read = (&mut self.reader)
+            .take(MAX_LINE_BYTES + 1)
+            .read_line(&mut line)?;
+        if read == 0 {
+            return Err(NetError::Disconnected);
+        }
+        if !line.ends_with('\n') && read as u64 > MAX_LINE_BYTES {
+            return Err(NetError::LineTooLong);
+        }
+        Ok(from_json(line.trim_end())?)
+    }
+}
+
+pub(crate) fn send_line<T: Serialize>(stream: &mut TcpStream, value: &T) -> Result<(), NetError> {
+    writeln!(stream, "{}", to_json(value))?;
+    Ok(())
+}
+
+pub(crate) type Inbound<T> = mpsc::Receiver<(usize, Result<T, NetError>)>;
+
+// Reads every connection on its own thread and funnels what arrives into one channel,
+// tagged with the connection's index. Returns the connections' write halves.
+pub(crate) fn fan_in<T: DeserializeOwned + Send + 'static>(
+    connections: Vec<Connection>,
+) -> Result<(Vec<TcpStream>, Inbound<T>), NetError> {
+    let (sender, receiver) = mpsc::channel();
+    let mut writers = Vec::new();
+    for (index, mut connection) in connections.into_iter().enumerate() {
+        writers.push(connection.writer.try_clone()?);
+        let sender = sender.clone();
+        thread::spawn(move || loop {
+            let received = connection.receive::<T>();
+            let closed = matches!(
+                received,
+                Err(NetError::Disconnected | NetError::Io(_) | NetError::LineTooLong)
+            );
+            if sender.send((index, received)).is_err() || closed {
+                break;
+            }
+        });
+    }
+    Ok((writers, receiver))
+}
+
+// Closing the sockets also stops their reader threads and tells the other side we are
+// done, however we got here.
+pub(crate) fn close(writers: &[TcpStream]) {
+    for writer in writers {
+        let _ = writer.shutdown(Shutdown::Both);
+    }
+}
+
+// Seats players as they connect and then runs their table. The host holds no keys: it
+// checks every signed message against its own `Table` and relays the ones that hold up,
+// so players' secret keys never leave their machines.
+#[derive(Debug)]
+pub struct TableHost {
+    config: TableConfig,
+    game_id: GameId,
+    table_id: TableId,
+    connections: Vec<Connection>,
+    joins: Vec<SignedMessage>,
+    turn_timeout: Duration,
+}
+
+impl TableHost {
+    pub fn new(config: TableConfig, game_id: GameId, table_id: TableId) -> Self {
+        TableHost {
+            config,
+            game_id,
+            table_id,
+            connections: Vec::new(),
+            joins: Vec::new(),
+            turn_timeout: TURN_TIMEOUT,
+        }
+    }
+
+    pub fn set_turn_timeout(&mut self, timeout: Duration) {
+        self.turn_timeout = timeout;
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.joins.len() >= self.config.seats
+    }
+
+    // What a new connection is greeted with.
+    pub fn welcome(&self) -> ServerMessage {
+        ServerMessage::Welcome {
+            game_id: self.game_id,
+            table_id: self.table_id,
+            config: self.config,
+        }
+    }
+
+    // Seats a connection that has been welcomed and sent `join`, if the join is valid.
+    pub fn seat(
+        &mut self,
+        mut connection: Connection,
+        join: SignedMessage,
+    ) -> Result<(), NetError> {
+        // The joins so far plus this one must be valid joins from distinct players.
+        let mut joins = self.joins.clone();
+        joins.push(join.clone());
+        if let Err(err) = Table::check_joins(self.game_id, &joins) {
+            let reason = err.to_string();
+            connection.send(&ServerMessage::Rejected {
+                reason: reason.clone(),
+            })?;
+            return Err(NetError::Rejected(reason));
+        }
+        self.connections.push(connection);
+        self.joins.push(join);
+        Ok(())
+    }
+
+    // Plays the table to the end and returns it.
+    pub fn run(self) -> Result<Table, PokerError> {
+        let mut table = Table::from_joins(self.config, self.game_id, self.table_id, &self.joins)?;
+        let (writers, receiver) = fan_in(self.connections)?;
+        let mut seats = Seats::new(writers, self.turn_timeout);
+        seats.broadcast(&ServerMessage::Started { joins: self.joins });
+        let result = relay(&mut table, &receiver, &mut seats, self.turn_timeout);
+        close(&seats.writers);
+        result.map(|()| table)
+    }
+}
+
+type Arrival = (SocketAddr, Result<(Connection, SignedMessage), NetError>);
+
+// Accepts connections on a thread of its own and greets each one on another, so a client
+// that is slow to join, or never does, holds up nobody else's seat.
+#[derive(Debug)]
+pub struct Lobby {
+    welcome: Arc<Mutex<ServerMessage>>,
+    arrivals: mpsc::Receiver<Arrival>,
+}
+
+impl Lobby {
+    // Starts greeting connections with `welcome` until the first table is filled.
+    pub fn open(listener: TcpListener, welcome: ServerMessage) -> Self {
+        let welcome = Arc::new(Mutex::new(welcome));
+        let (sender, arrivals) = mpsc::channel();
+        let greeting = Arc::clone(&welcome);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let Ok(peer) = stream.peer_addr() else {
+                    continue;
+                };
+                let welcome = greeting.lock().map(|welcome| welcome.clone());
+                let Ok(welcome) = welcome else {
+                    break;
+                };
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let _ = sender.send((peer, handshake(stream, &welcome)));
+                });
+            }
+        });
+        Lobby { welcome, arrivals }
+    }
+
+    // Seats arriving players at `host` until it is full, telling `report` how each went.
+    pub fn fill(
+        &self,
+        host: &mut TableHost,
+        mut report: impl FnMut(SocketAddr, Result<(), NetError>),
+    ) -> Result<(), NetError> {
+        if let Ok(mut welcome) = self.welcome.lock() {
+            *welcome = host.welcome();
+        }
+        while !host.is_full() {
+            let (peer, arrival) = self.arrivals.recv().map_err(|_| NetError::Disconnected)?;
+            report(
+                peer,
+                arrival.and_then(|(connection, join)| host.seat(connection, join)),
+            );
+        }
+        Ok(())
+    }
+}
+
+// Welcomes a new connection and reads its join, giving up once `JOIN_TIMEOUT` has passed
+// however slowly the bytes trickle in.
+fn handshake(
+    stream: TcpStream,
+    welcome: &ServerMessage,
+) -> Result<(Connection, SignedMessage), NetError> {
+    let mut connection = Connection::new(stream)?;
+    let socket = connection.writer.try_clone()?;
+    let (done, finished) = mpsc::channel::<()>();
+    let watchdog = thread::spawn(move || {
+        let expired = finished.recv_timeout(JOIN_TIMEOUT) == Err(RecvTimeoutError::Timeout);
+        if expired {
+            let _ = socket.shutdown(Shutdown::Both);
+        }
+        expired
+    });
+    let join = connection
+        .send(welcome)
+        .and_then(|()| connection.receive::<SignedMessage>());
+    drop(done);
+    if watchdog.join().unwrap_or(true) {
+        return Err(NetError::Io(ErrorKind::TimedOut));
+    }
+    Ok((connection, join?))
+}
+
+// The players' write halves. A seat whose connection fails is dropped, so one player
+// leaving never takes the table down for everyone else.
+struct Seats {
+    writers: Vec<TcpStream>,
+    gone: Vec<bool>,
+}
+
+impl Seats {
+    fn new(writers: Vec<TcpStream>, write_timeout: Duration) -> Self {
+        let gone = writers
+            .iter()
+            .map(|writer| writer.set_write_timeout(Some(write_timeout)).is_err())
+            .collect();
+        Seats { writers, gone }
+    }
+
+    fn send<T: Serialize>(&mut self, seat: usize, value: &T) {
+        if !self.gone[seat] && send_line(&mut self.writers[seat], value).is_err() {
+            self.leave(seat);
+        }
+    }
+
+    fn broadcast<T: Serialize>(&mut self, value: &T) {
+        for seat in 0..self.writers.len() {
+            self.send(seat, value);
+        }
+    }
+
+    fn leave(&mut self, seat: usize) {
+        self.gone[seat] = true;
+        let _ = self.writers[seat].shutdown(Shutdown::Both);
+    }
+}
+
+// Checks and relays messages until the table is finished. Seats that owe the table a
+// message and have left, or have not sent it within `turn_timeout` of the last accepted
+// message, are timed out, so nobody can stall the table.
+fn relay(
+    table: &mut Table,
+    receiver: &Inbound<SignedMessage>,
+    seats: &mut Seats,
+    turn_timeout: Duration,
+) -> Result<(), PokerError> {
+    let mut deadline = Instant::now() + turn_timeout;
+    while !table.is_finished() {
+        if !seats.gone.contains(&false) {
+            return Err(NetError::Disconnected.into());
+        }
+        let owing: Vec<usize> = (0..table.config().seats)
+            .filter(|&seat| table.expected(seat).is_some())
+            .collect();
+        let left: Vec<usize> = owing
+            .iter()
+            .copied()
+            .filter(|&seat| seats.gone[seat])
+            .collect();
+        let timed_out = if !left.is_empty() {
+            left
+        } else {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok((seat, received)) => {
+                    let rejected = match received {
+                        Ok(signed) => match table.receive(&signed) {
+                            Ok(_) => {
+                                seats.broadcast(&ServerMessage::Relayed {
+                                    message: Box::new(signed),
+                                });
+                                deadline = Instant::now() + turn_timeout;
+                                continue;
+                            }
+                            Err(err) => err.to_string(),
+                        },
+                        Err(NetError::Wire(err)) => err.to_string(),
+                        Err(_) => {
+                            seats.leave(seat);
+                            continue;
+                        }
+                    };
+                    seats.send(seat, &ServerMessage::Rejected { reason: rejected });
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => owing,
+                Err(RecvTimeoutError::Disconnected) => return Err(NetError::Disconnected.into()),
+            }
+        };
+        table.time_out(&timed_out)?;
+        seats.broadcast(&ServerMessage::TimedOut { seats: timed_out });
+        deadline = Instant::now() + turn_timeout;
+    }
+    let stacks = table.state().stacks().to_vec();
+    seats.broadcast(&ServerMessage::Finished { stacks });
+    Ok(())
+}
+
+// What a client learned from one message from the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    Started,
+    Accepted {
+        seat: usize,
+        effects: Vec<Effect>,
+    },
+    // A message the host relayed, or a seating it announced, that our own table refuses.
+    // An honest host never sends these, so it is either broken or cheating.
+    Invalid {
+        seat: Option<usize>,
+        error: PokerError,
+    },
+    Rejected {
+        reason: String,
+    },
+    // Seats the host timed out, as our own table agrees they owed it something.
+    TimedOut {
+        seats: Vec<usize>,
+        effects: Vec<Effect>,
+    },
+    Finished {
+        stacks: Vec<Chips>,
+    },
+}
+
+// A player at a hosted table. The client keeps its own `Table` from what the host relays,
+// so it checks every opponent's proof and action itself instead of trusting the host.
+// Its deck key never leaves it, so only the client can open its own hole cards.
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    player: Player,
+    deck_key: DeckKey,
+    // Our hole cards in the current hand, once they are dealt.
+    hole_cards: Option<Vec<Card>>,
+    game_id: GameId,
+    table_id: TableId,
+    config: TableConfig,
+    table: Option<Table>,
+    seat: Option<usize>,
+    // Set while our last message has not come back from the host.
+    awaiting: bool,
+}
+
+impl Client {
+    pub fn join(
+        addr: impl ToSocketAddrs,
+        name: &str,
+        mut player: Player,
+    ) -> Result<Self, NetError> {
+        let mut connection = Connection::connect(addr)?;
+        let ServerMessage::Welcome {
+            game_id,
+            table_id,
+            config,
+        } = connection.receive()?
+        else {
+            return Err(NetError::UnexpectedMessage);
+        };
+        let deck_key = DeckKey::generate(OsRng);
+        let join = player.sign_message(
+            &game_id,
+            Message::Join {
+                name: name.to_string(),
+                deck_key: Box::new(deck_key.announce(&player.public_key())),
+            },
+        );
+        connection.send(&join)?;
+        Ok(Client {
+            connection,
+            player,
+            deck_key,
+            hole_cards: None,
+            game_id,
+            table_id,
+            config,
+            table: None,
+            seat: None,
+            awaiting: false,
+        })
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn table_id(&self) -> TableId {
+        self.table_id
+    }
+
+    pub fn config(&self) -> &TableConfig {
+        &self.config
+    }
+
+    pub fn table(&self) -> Option<&Table> {
+        self.table.as_ref()
+    }
+
+    pub fn seat(&self) -> Option<usize> {
+        self.seat
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn hole_cards(&self) -> Option<&[Card]> {
+        self.hole_cards.as_deref()
+    }
+
+    // What we owe the table, unless we are still waiting for our last message to come back.
+    pub fn expected(&self) -> Option<Expected> {
+        if self.awaiting {
+            return None;
+        }
+        self.table.as_ref()?.expected(self.seat?)
+    }
+
+    // Sends whatever we owe the table, asking `decide` when it is a betting decision.
+    // Returns whether anything was sent.
+    pub fn respond(
+        &mut self,
+        decide: impl FnOnce(&Table, usize) -> Action,
+    ) -> Result<bool, NetError> {
+        let (Some(expected), Some(table), Some(seat)) = (self.expected(), &self.table, self.seat)
+        else {
+            return Ok(false);
+        };
+        let message = expected
+            .answer(table.state(), &mut self.player, &mut self.deck_key)
+            .unwrap_or_else(|| Message::Action {
+                action: decide(table, seat),
+            });
+        let signed = self.player.sign_message(&self.game_id, message);
+        self.connection.send(&signed)?;
+        self.awaiting = true;
+        Ok(true)
+    }
+
+    // Waits for the next message from the host and checks it against our own table.
+    pub fn poll(&mut self) -> Result<Update, NetError> {
+        match self.connection.receive()? {
+            ServerMessage::Started { joins } => {
+                let table =
+                    match Table::from_joins(self.config, self.game_id, self.table_id, &joins) {
+                        Ok(table) => table,
+                        Err(error) => return Ok(Update::Invalid { seat: None, error }),
+                    };
+                self.seat = table.seat_of(&self.player.public_key());
+                self.table = Some(table);
+                if self.seat.is_none() {
+                    return Ok(Update::Invalid {
+                        seat: None,
+                        error: PokerError::UnknownPlayer,
+                    });
+                }
+                Ok(Update::Started)
+            }
+            ServerMessage::Relayed { message } => {
+                let table = self.table.as_mut().ok_or(NetError::UnexpectedMessage)?;
+                if message.public == self.player.public_key() {
+                    self.awaiting = false;
+                }
+                let (seat, effects) = match table.receive(&message) {
+                    Ok(accepted) => accepted,
+                    Err(error) => {
+                        return Ok(Update::Invalid {
+                            seat: table.seat_of(&message.public),
+                            error,
+                        })
+                    }
+                };
+                for effect in &effects {
+                    match effect {
+                        Effect::HandStarted { .. } => self.hole_cards = None,
+                        Effect::HoleCardsDealt => {
+                            let ours = self.seat.ok_or(NetError::UnexpectedMessage)?;
+                            match table.state().open_hole_cards(ours, &mut self.deck_key) {
+                                Ok(cards) => self.hole_cards = Some(cards),
+                                Err(error) => return Ok(Update::Invalid { seat: None, error }),
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Update::Accepted { seat, effects })
+            }
+            ServerMessage::Rejected { reason } => {
+                self.awaiting = false;
+                Ok(Update::Rejected { reason })
+            }
+            ServerMessage::TimedOut { seats } => {
+                let table = self.table.as_mut().ok_or(NetError::UnexpectedMessage)?;
+                Ok(match table.time_out(&seats) {
+                    Ok(effects) => Update::TimedOut { seats, effects },
+                    Err(error) => Update::Invalid { seat: None, error },
+                })
+            }
+            ServerMessage::Finished { stacks } => Ok(Update::Finished { stacks }),
+            ServerMessage::Welcome { .. } => Err(NetError::UnexpectedMessage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mental::MentalError;
+    use std::net::{SocketAddr, TcpListener};
+
+    fn config() -> TableConfig {
+        TableConfig {
+            seats: 2,
+            stack: 100,
+            small_blind: 5,
+            big_blind: 10,
+            max_hands: Some(3),
+        }
+    }
+
+    fn host(
+        listener: TcpListener,
+        turn_timeout: Duration,
+    ) -> thread::JoinHandle<Result<Table, PokerError>> {
+        thread::spawn(move || {
+            let mut host = TableHost::new(config(), [8; 32], 1);
+            host.set_turn_timeout(turn_timeout);
+            let lobby = Lobby::open(listener, host.welcome());
+            lobby.fill(&mut host, |_, _| {}).unwrap();
+            host.run()
+        })
+    }
+
+    fn check_or_call(table: &Table, seat: usize) -> Action {
+        match table.state().betting().unwrap().amount_to_call(seat) {
+            0 => Action::Check,
+            _ => Action::Call,
+        }
+    }
+
+    fn bot(addr: SocketAddr, name: &str) -> thread::JoinHandle<Vec<Chips>> {
+        let name = name.to_string();
+        thread::spawn(move || {
+            let mut client = Client::join(addr, &name, Player::new()).unwrap();
+            loop {
+                client.respond(check_or_call).unwrap();
+                match client.poll().unwrap() {
+                    Update::Finished { stacks } => {
+                        assert_eq!(client.table().unwrap().state().stacks(), &stacks[..]);
+                        return stacks;
+                    }
+                    Update::Accepted { effects, .. }
+                        if effects.contains(&Effect::HoleCardsDealt) =>
+                    {
+                        assert_eq!(client.hole_cards().map(<[Card]>::len), Some(2));
+                    }
+                    update @ (Update::Invalid { .. } | Update::Rejected { .. }) => {
+                        panic!("{:?}", update)
+                    }
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_hosted_table() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = host(listener, TURN_TIMEOUT);
+        // Somebody who connects and never joins keeps nobody else from being seated.
+        let _silent = TcpStream::connect(addr).unwrap();
+        let (alice, bob) = (bot(addr, "Alice"), bot(addr, "Bob"));
+        let (alice, bob) = (alice.join().unwrap(), bob.join().unwrap());
+
+        let table = host.join().unwrap().unwrap();
+        assert_eq!(table.state().hand_number(), 3);
+        assert_eq!(table.state().stacks(), &alice[..]);
+        assert_eq!(alice, bob);
+        assert_eq!(alice.iter().sum::<Chips>(), 200);
+    }
+
+    #[test]
+    fn test_missing_reveal_is_timed_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = host(listener, Duration::from_secs(1));
+        let alice = bot(addr, "Alice");
+        // Bob commits but never reveals.
+        let mut bob = Client::join(addr, "Bob", Player::new()).unwrap();
+        let stacks = loop {
+            if bob.expected() == Some(Expected::Commit) {
+                bob.respond(check_or_call).unwrap();
+            }
+            match bob.poll().unwrap() {
+                Update::TimedOut { seats, .. } => {
+                    assert_eq!(seats, vec![bob.seat().unwrap()])
+                }
+                Update::Finished { stacks } => break stacks,
+                update @ (Update::Invalid { .. } | Update::Rejected { .. }) => {
+                    panic!("{:?}", update)
+                }
+                _ => {}
+            }
+        };
+        let mut expected = vec![200, 200];
+        expected[bob.seat().unwrap()] = 0;
+        assert_eq!(stacks, expected);
+        assert_eq!(alice.join().unwrap(), expected);
+        assert_eq!(
+            host.join().unwrap().unwrap().state().stacks(),
+            &expected[..]
+        );
+    }
+
+    #[test]
+    fn test_bad_joins_and_messages_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = host(listener, TURN_TIMEOUT);
+
+        // A join signed for another game does not get a seat.
+        let mut stranger = Connection::connect(addr).unwrap();
+        let _: ServerMessage = stranger.receive().unwrap();
+        let mut player = Player::new();
+        stranger
+            .send(&player.sign_message(
+                &[0; 32],
+                Message::Join {
+                    name: "Mallory".to_string(),
+                    deck_key: Box::new(DeckKey::generate(OsRng).announce(&player.public_key())),
+                },
+            ))
+            .unwrap();
+        assert!(matches!(
+            stranger.receive().unwrap(),
+            ServerMessage::Rejected { .. }
+        ));
+
+        let alice = bot(addr, "Alice");
+        let mut bob = Client::join(addr, "Bob", Player::new()).unwrap();
+        assert_eq!(bob.poll(), Ok(Update::Started));
+        // Betting before the hand is dealt is refused, and only Bob hears about it.
+        let signed = bob.player.sign_message(
+            &bob.game_id,
+            Message::Action {
+                action: Action::AllIn,
+            },
+        );
+        bob.connection.send(&signed).unwrap();
+        loop {
+            match bob.poll().unwrap() {
+                Update::Rejected { .. } => break,
+                Update::Accepted { .. } => {}
+                update => panic!("{:?}", update),
+            }
+        }
+        // Bob leaving forfeits his seat, and Alice's game ends normally.
+        let bob_seat = bob.seat().unwrap();
+        drop(bob);
+        let mut expected = vec![200, 200];
+        expected[bob_seat] = 0;
+        assert_eq!(alice.join().unwrap(), expected);
+        assert_eq!(
+            host.join().unwrap().unwrap().state().stacks(),
+            &expected[..]
+        );
+    }
+
+    #[test]
+    fn test_long_lines_are_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let sender = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let line = vec![b'x'; MAX_LINE_BYTES as usize + 1];
+            let _ = stream.write_all(&line);
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let mut connection = Connection::new(stream).unwrap();
+        assert_eq!(
+            connection.receive::<ServerMessage>(),
+            Err(NetError::LineTooLong)
+        );
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_client_catches_forged_shares() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut client = Client::join(addr, "Alice", Player::new()).unwrap();
+            loop {
+                client.respond(check_or_call).unwrap();
+                match client.poll().unwrap() {
+                    update @ Update::Invalid { .. } => return (update, client.hole_cards),
+                    Update::Finished { .. } => panic!("forgery went unnoticed"),
+                    _ => {}
+                }
+            }
+        });
+
+        // A host that relays honestly, except that it forges its accomplice's flop shares.
+        let game_id = [9; 32];
+        let (stream, _) = listener.accept().unwrap();
+        let mut alice = Connection::new(stream).unwrap();
+        alice
+            .send(&ServerMessage::Welcome {
+                game_id,
+                table_id: 0,
+                config: config(),
+            })
+            .unwrap();
+        let mut mallory = Player::new();
+        let mut key = DeckKey::generate(OsRng);
+        let deck_key = Box::new(key.announce(&mallory.public_key()));
+        let joins = vec![
+            alice.receive().unwrap(),
+            mallory.sign_message(
+                &game_id,
+                Message::Join {
+                    name: "Mallory".to_string(),
+                    deck_key,
+                },
+            ),
+        ];
+        let mut table = Table::from_joins(config(), game_id, 0, &joins).unwrap();
+        alice.send(&ServerMessage::Started { joins }).unwrap();
+        let mut dealt = false;
+        loop {
+            let signed = match table.expected(1) {
+                Some(expected @ Expected::Shares(_)) if dealt => {
+                    // Even with every message in hand, Mallory cannot see Alice's cards.
+                    assert!(table.state().open_hole_cards(0, &mut key).is_err());
+                    let Some(Message::Shares { mut shares }) =
+                        expected.answer(table.state(), &mut mallory, &mut key)
+                    else {
+                        unreachable!()
+                    };
+                    shares.swap(0, 1);
+                    let forged = mallory.sign_message(&game_id, Message::Shares { shares });
+                    alice
+                        .send(&ServerMessage::Relayed {
+                            message: Box::new(forged),
+                        })
+                        .unwrap();
+                    break;
+                }
+                Some(expected) => {
+                    let message = expected
+                        .answer(table.state(), &mut mallory, &mut key)
+                        .unwrap_or_else(|| Message::Action {
+                            action: check_or_call(&table, 1),
+                        });
+                    mallory.sign_message(&game_id, message)
+                }
+                None => alice.receive().unwrap(),
+            };
+            let (_, effects) = table.receive(&signed).unwrap();
+            dealt |= effects.contains(&Effect::HoleCardsDealt);
+            alice
+                .send(&ServerMessage::Relayed {
+                    message: Box::new(signed),
+                })
+                .unwrap();
+        }
+        let (update, hole_cards) = client.join().unwrap();
+        assert_eq!(
+            update,
+            Update::Invalid {
+                seat: Some(1),
+                error: PokerError::Mental(MentalError::InvalidShare(1))
+            }
+        );
+        // Alice opened her own cards before the flop.
+        assert_eq!(hole_cards.map(|cards| cards.len()), Some(2));
+    }
+}
@@ -0,0 +1,709 @@
+use crate::betting::Action;
+use crate::card::Card;
+use crate::engine::Effect;
+use crate::error::PokerError;
+use crate::mental::DeckKey;
+use crate::net::{
+    close, fan_in, send_line, Connection, Inbound, NetError, Update, JOIN_TIMEOUT, TURN_TIMEOUT,
+};
+use crate::player::Player;
+use crate::table::{Expected, Table, TableConfig};
+use crate::transcript::{GameId, TableId};
+use crate::wire::{hex_encoded, Message, SignedMessage};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long to keep dialling a peer that is not listening yet.
+pub const DIAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerError {
+    Net(NetError),
+    // Our own table could not be set up from the peers' joins.
+    Table(PokerError),
+    // A peer sent a message our table refuses, so they are cheating or playing another game.
+    Invalid { seat: usize, error: PokerError },
+    // A peer's table finished a hand differently from ours.
+    Diverged { seat: usize, hand: u32 },
+    // Nothing came in for a whole turn timeout while we were waiting on these seats.
+    TimedOut { seats: Vec<usize> },
+}
+
+impl fmt::Display for PeerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerError::Net(err) => write!(f, "{}", err),
+            PeerError::Table(err) => write!(f, "{}", err),
+            PeerError::Invalid { seat, error } => {
+                write!(f, "seat {} sent an invalid message: {}", seat, error)
+            }
+            PeerError::Diverged { seat, hand } => {
+                write!(f, "seat {} disagrees with us about hand {}", seat, hand)
+            }
+            PeerError::TimedOut { seats } => write!(f, "seats {:?} timed out", seats),
+        }
+    }
+}
+
+impl std::error::Error for PeerError {}
+
+impl From<NetError> for PeerError {
+    fn from(err: NetError) -> Self {
+        PeerError::Net(err)
+    }
+}
+
+// What peers send each other, one JSON message per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerMessage {
+    Hello {
+        join: Box<SignedMessage>,
+    },
+    Signed {
+        message: Box<SignedMessage>,
+    },
+    // The sender's `GameState::history` after `hand`.
+    Checkpoint {
+        hand: u32,
+        #[serde(with = "hex_encoded")]
+        history: [u8; 32],
+    },
+}
+
+// Peers that dial us keep trying for `DIAL_TIMEOUT`, so we wait as long for them to show up.
+fn accept(listener: &TcpListener, deadline: Instant) -> Result<Connection, NetError> {
+    listener.set_nonblocking(true)?;
+    let accepted = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break Ok(stream),
+            Err(err) if err.kind() != ErrorKind::WouldBlock => break Err(err.into()),
+            Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(100)),
+            Err(_) => break Err(NetError::Io(ErrorKind::TimedOut)),
+        }
+    };
+    listener.set_nonblocking(false)?;
+    let stream = accepted?;
+    stream.set_nonblocking(false)?;
+    Connection::new(stream)
+}
+
+fn dial(addr: SocketAddr) -> Result<Connection, NetError> {
+    let start = Instant::now();
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return Connection::new(stream),
+            Err(_) if start.elapsed() < DIAL_TIMEOUT => thread::sleep(Duration::from_millis(100)),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+// A seat at a table with no host. Every peer sends its signed messages straight to all the
+// others and runs its own `Table`, so each one checks every proof and action itself.
+// Messages from different peers may arrive in any order: one is held back until the table
+// is waiting on its sender, as long as it is at most one hand ahead, and after every hand
+// the peers compare their tables' histories to catch any divergence. Only the peer itself
+// holds its deck key, so nobody else can open its hole cards until it shows them.
+#[derive(Debug)]
+pub struct Peer {
+    player: Player,
+    deck_key: DeckKey,
+    // Our hole cards in the current hand, once they are dealt.
+    hole_cards: Option<Vec<Card>>,
+    game_id: GameId,
+    table: Table,
+    seat: usize,
+    // The seat at the other end of each link.
+    links: Vec<usize>,
+    writers: Vec<TcpStream>,
+    inbound: Inbound<PeerMessage>,
+    // Each seat's messages that arrived before our table was ready for them, oldest first.
+    pending: Vec<VecDeque<SignedMessage>>,
+    // Our history after each hand, and peers' histories we could not check yet.
+    histories: HashMap<u32, [u8; 32]>,
+    reported: HashMap<(usize, u32), [u8; 32]>,
+    // The last hand each seat has confirmed our history for.
+    confirmed: Vec<u32>,
+    // Seats that have hung up on us.
+    gone: Vec<bool>,
+    updates: VecDeque<Update>,
+    turn_timeout: Duration,
+    // When we give up on whoever we are waiting for, unless the table moves on first.
+    deadline: Instant,
+}
+
+impl Peer {
+    // Meets the other peers: dials every address in `dial` and accepts everyone else on
+    // `listener`. Seats go in public-key order, so all peers agree on them.
+    pub fn connect(
+        config: TableConfig,
+        game_id: GameId,
+        table_id: TableId,
+        name: &str,
+        mut player: Player,
+        listener: &TcpListener,
+        dial_addrs: &[SocketAddr],
+    ) -> Result<Self, PeerError> {
+        let accepting = config
+            .seats
+            .checked_sub(dial_addrs.len() + 1)
+            .ok_or(PeerError::Table(PokerError::InvalidJoin))?;
+        let mut connections = Vec::new();
+        for addr in dial_addrs {
+            connections.push(dial(*addr)?);
+        }
+        let deadline = Instant::now() + DIAL_TIMEOUT;
+        for _ in 0..accepting {
+            connections.push(accept(listener, deadline)?);
+        }
+
+        let deck_key = DeckKey::generate(OsRng);
+        let join = player.sign_message(
+            &game_id,
+            Message::Join {
+                name: name.to_string(),
+                deck_key: Box::new(deck_key.announce(&player.public_key())),
+            },
+        );
+        for connection in &mut connections {
+            connection.send(&PeerMessage::Hello {
+                join: Box::new(join.clone()),
+            })?;
+        }
+        let mut joins = vec![join];
+        let mut publics = Vec::new();
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        for connection in &mut connections {
+            let left = deadline.saturating_duration_since(Instant::now());
+            connection.set_timeout(Some(left.max(Duration::from_millis(1))))?;
+            let hello = connection.receive().map_err(|err| match err {
+                NetError::Io(ErrorKind::WouldBlock) => NetError::Io(ErrorKind::TimedOut),
+                err => err,
+            })?;
+            let PeerMessage::Hello { join } = hello else {
+                return Err(NetError::UnexpectedMessage.into());
+            };
+            publics.push(join.public);
+            joins.push(*join);
+        }
+        for connection in &connections {
+            connection.set_timeout(None)?;
+        }
+        joins.sort_by(|a, b| a.public.as_ref().cmp(b.public.as_ref()));
+
+        let table =
+            Table::from_joins(config, game_id, table_id, &joins).map_err(PeerError::Table)?;
+        let seat = table
+            .seat_of(&player.public_key())
+            .expect("our own join is seated");
+        let links = publics
+            .iter()
+            .map(|public| table.seat_of(public).expect("every peer's join is seated"))
+            .collect();
+        let (writers, inbound) = fan_in(connections)?;
+        Ok(Peer {
+            player,
+            deck_key,
+            hole_cards: None,
+            game_id,
+            table,
+            seat,
+            links,
+            writers,
+            inbound,
+            pending: vec![VecDeque::new(); config.seats],
+            histories: HashMap::new(),
+            reported: HashMap::new(),
+            confirmed: vec![0; config.seats],
+            gone: vec![false; config.seats],
+            updates: VecDeque::new(),
+            turn_timeout: TURN_TIMEOUT,
+            deadline: Instant::now() + TURN_TIMEOUT,
+        })
+    }
+
+    pub fn set_turn_timeout(&mut self, timeout: Duration) {
+        self.turn_timeout = timeout;
+        self.deadline = Instant::now() + timeout;
+    }
+
+    pub fn game_id(&self) -> &GameId {
+        &self.game_id
+    }
+
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    pub fn seat(&self) -> usize {
+        self.seat
+    }
+
+    pub fn hole_cards(&self) -> Option<&[Card]> {
+        self.hole_cards.as_deref()
+    }
+
+    // Plays on until something happens, making our own moves along the way and asking
+    // `decide` for betting decisions. After each hand we hold our next move until every
+    // peer has confirmed our history, and once the table is over returns `Update::Finished`.
+    pub fn poll(
+        &mut self,
+        mut decide: impl FnMut(&Table, usize) -> Action,
+    ) -> Result<Update, PeerError> {
+        loop {
+            if let Some(update) = self.updates.pop_front() {
+                return Ok(update);
+            }
+            if self.settled() && self.table.is_finished() {
+                close(&self.writers);
+                return Ok(Update::Finished {
+                    stacks: self.table.state().stacks().to_vec(),
+                });
+            }
+            // Everything a peer sent before hanging up still counts, so it has only let us
+            // down once we wait on it for a move or for its checkpoint.
+            let stranded = (0..self.gone.len()).any(|seat| {
+                self.gone[seat]
+                    && self.table.expected(seat).is_some()
+                    && (self.settled() || self.confirmed[seat] < self.hands())
+            });
+            if stranded {
+                return Err(NetError::Disconnected.into());
+            }
+            if let Some(expected) = self.table.expected(self.seat).filter(|_| self.settled()) {
+                self.play(expected, &mut decide)?;
+                continue;
+            }
+
+            let wait = self.deadline.saturating_duration_since(Instant::now());
+            let (link, received) = match self.inbound.recv_timeout(wait) {
+                Ok(received) => received,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(PeerError::TimedOut {
+                        seats: self.waiting_on(),
+                    })
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(NetError::Disconnected.into()),
+            };
+            let seat = self.links[link];
+            match received {
+                Ok(PeerMessage::Signed { message }) => {
+                    if message.public != self.table.state().players()[seat].1 {
+                        return Err(PeerError::Invalid {
+                            seat,
+                            error: PokerError::UnknownPlayer,
+                        });
+                    }
+                    if !self.may_queue(seat, &message) {
+                        return Err(PeerError::Invalid {
+                            seat,
+                            error: PokerError::OutOfPhase,
+                        });
+                    }
+                    self.pending[seat].push_back(*message);
+                    self.drain()?;
+                }
+                Ok(PeerMessage::Checkpoint { hand, history }) => {
+                    // Nobody can finish a hand before we have started it ourselves.
+                    if hand == 0 || hand > self.table.state().hand_number() {
+                        return Err(PeerError::Invalid {
+                            seat,
+                            error: PokerError::OutOfPhase,
+                        });
+                    }
+                    self.check(seat, hand, history)?
+                }
+                Ok(PeerMessage::Hello { .. }) => return Err(NetError::UnexpectedMessage.into()),
+                Err(NetError::Disconnected) => self.gone[seat] = true,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    fn play(
+        &mut self,
+        expected: Expected,
+        decide: &mut impl FnMut(&Table, usize) -> Action,
+    ) -> Result<(), PeerError> {
+        let message = expected
+            .answer(self.table.state(), &mut self.player, &mut self.deck_key)
+            .unwrap_or_else(|| Message::Action {
+                action: decide(&self.table, self.seat),
+            });
+        let signed = self.player.sign_message(&self.game_id, message);
+        let (seat, effects) = self.table.receive(&signed).map_err(PeerError::Table)?;
+        self.send_all(&PeerMessage::Signed {
+            message: Box::new(signed),
+        });
+        self.accepted(seat, effects)
+    }
+
+    // Whether `seat` may be one hand ahead of us with `signed`. A peer cannot get further
+    // ahead than committing to the next hand before we finish this one, so while we are
+    // between hands its commitment must be the one we are waiting for, and nothing can
+    // follow a commitment we are holding back. Each move our table is still waiting for
+    // from someone else lets `seat` get at most one message further ahead, so it cannot
+    // have more held back than that.
+    fn may_queue(&self, seat: usize, signed: &SignedMessage) -> bool {
+        let queued_commit = self.pending[seat]
+            .iter()
+            .any(|queued| matches!(queued.message, Message::Commit { .. }));
+        let owed = (0..self.pending.len())
+            .filter(|&other| other != seat && self.table.expected(other).is_some())
+            .count();
+        if queued_commit || self.pending[seat].len() > owed {
+            return false;
+        }
+        !matches!(signed.message, Message::Commit { .. })
+            || !self.table.state().is_idle()
+            || self.table.expected(seat) == Some(Expected::Commit)
+    }
+
+    // Applies held-back messages for as long as the table is waiting on their senders.
+    fn drain(&mut self) -> Result<(), PeerError> {
+        loop {
+            let ready = (0..self.pending.len()).find(|&seat| {
+                !self.pending[seat].is_empty() && self.table.expected(seat).is_some()
+            });
+            let Some(seat) = ready else {
+                return Ok(());
+            };
+            let signed = self.pending[seat].pop_front().expect("seat has a message");
+            match self.table.receive(&signed) {
+                Ok((_, effects)) => self.accepted(seat, effects)?,
+                Err(error) => return Err(PeerError::Invalid { seat, error }),
+            }
+        }
+    }
+
+    fn accepted(&mut self, seat: usize, effects: Vec<Effect>) -> Result<(), PeerError> {
+        self.deadline = Instant::now() + self.turn_timeout;
+        for effect in &effects {
+            match effect {
+                Effect::HandStarted { .. } => self.hole_cards = None,
+                Effect::HoleCardsDealt => {
+                    let cards = self
+                        .table
+                        .state()
+                        .open_hole_cards(self.seat, &mut self.deck_key)
+                        .map_err(PeerError::Table)?;
+                    self.hole_cards = Some(cards);
+                }
+                _ => {}
+            }
+        }
+        if effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::HandFinished { .. }))
+        {
+            let hand = self.table.state().hand_number();
+            let history = *self.table.state().history();
+            self.send_all(&PeerMessage::Checkpoint { hand, history });
+            self.histories.insert(hand, history);
+            for other in 0..self.confirmed.len() {
+                if let Some(theirs) = self.reported.remove(&(other, hand)) {
+                    self.compare(other, hand, theirs)?;
+                }
+            }
+        }
+        self.updates.push_back(Update::Accepted { seat, effects });
+        Ok(())
+    }
+
+    fn check(&mut self, seat: usize, hand: u32, history: [u8; 32]) -> Result<(), PeerError> {
+        if self.histories.contains_key(&hand) {
+            self.compare(seat, hand, history)
+        } else {
+            self.reported.insert((seat, hand), history);
+            Ok(())
+        }
+    }
+
+    fn compare(&mut self, seat: usize, hand: u32, theirs: [u8; 32]) -> Result<(), PeerError> {
+        if self.histories.get(&hand) != Some(&theirs) {
+            return Err(PeerError::Diverged { seat, hand });
+        }
+        if hand > self.confirmed[seat] {
+            self.confirmed[seat] = hand;
+            self.deadline = Instant::now() + self.turn_timeout;
+        }
+        Ok(())
+    }
+
+    // Writing fails only once a peer has hung up, and reading its link tells us why.
+    fn send_all(&mut self, message: &PeerMessage) {
+        for writer in &mut self.writers {
+            let _ = send_line(writer, message);
+        }
+    }
+
+    // The number of hands our table has finished.
+    fn hands(&self) -> u32 {
+        self.histories.len() as u32
+    }
+
+    // The other seats that owe our table a move or a checkpoint.
+    fn waiting_on(&self) -> Vec<usize> {
+        let hands = self.hands();
+        (0..self.confirmed.len())
+            .filter(|&seat| seat != self.seat)
+            .filter(|&seat| self.table.expected(seat).is_some() || self.confirmed[seat] < hands)
+            .collect()
+    }
+
+    // Whether every peer has confirmed our history for every finished hand.
+    fn settled(&self) -> bool {
+        let hands = self.hands();
+        (0..self.confirmed.len()).all(|seat| seat == self.seat || self.confirmed[seat] >= hands)
+    }
+}
+
+// Hang up on everyone, so a peer that gives up never leaves the others waiting on it.
+impl Drop for Peer {
+    fn drop(&mut self) {
+        close(&self.writers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::Chips;
+    use std::thread::JoinHandle;
+
+    // A peer's final stacks and history.
+    type Outcome = Result<(Vec<Chips>, [u8; 32]), PeerError>;
+
+    fn config(stack: Chips) -> TableConfig {
+        TableConfig {
+            seats: 3,
+            stack,
+            small_blind: 5,
+            big_blind: 10,
+            max_hands: Some(2),
+        }
+    }
+
+    fn check_or_call(table: &Table, seat: usize) -> Action {
+        match table.state().betting().unwrap().amount_to_call(seat) {
+            0 => Action::Check,
+            _ => Action::Call,
+        }
+    }
+
+    // Starts one loopback peer per config; each dials the ones started before it.
+    fn run_peers(configs: &[TableConfig]) -> Vec<JoinHandle<Outcome>> {
+        let listeners: Vec<TcpListener> = configs
+            .iter()
+            .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let addrs: Vec<SocketAddr> = listeners
+            .iter()
+            .map(|listener| listener.local_addr().unwrap())
+            .collect();
+        listeners
+            .into_iter()
+            .zip(configs)
+            .enumerate()
+            .map(|(i, (listener, &config))| {
+                let dial = addrs[..i].to_vec();
+                thread::spawn(move || {
+                    let name = format!("Peer {}", i);
+                    let mut peer =
+                        Peer::connect(config, [5; 32], 0, &name, Player::new(), &listener, &dial)?;
+                    loop {
+                        match peer.poll(check_or_call)? {
+                            Update::Finished { stacks } => {
+                                return Ok((stacks, *peer.table().state().history()))
+                            }
+                            Update::Accepted { effects, .. }
+                                if effects.contains(&Effect::HoleCardsDealt) =>
+                            {
+                                assert_eq!(peer.hole_cards().map(<[Card]>::len), Some(2));
+                                // Everyone else's cards stay closed to us.
+                                let state = peer.table.state().clone();
+                                for other in (0..config.seats).filter(|&seat| seat != peer.seat) {
+                                    assert!(state
+                                        .open_hole_cards(other, &mut peer.deck_key)
+                                        .is_err());
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_peers_agree() {
+        let results: Vec<_> = run_peers(&[config(100); 3])
+            .into_iter()
+            .map(|peer| peer.join().unwrap().unwrap())
+            .collect();
+        assert_eq!(results[0].0.iter().sum::<Chips>(), 300);
+        assert!(results.iter().all(|result| result == &results[0]));
+    }
+
+    // How a raw connection speaks for its seat once the peers have said hello.
+    type Script = Box<dyn FnOnce(&mut Connection, &mut Player) + Send>;
+
+    // Seats an honest peer opposite a raw connection that speaks for the other seat with
+    // `send`, and returns how the honest peer's game ended.
+    fn against(send: impl FnOnce(&mut Connection, &mut Player) + Send + 'static) -> Outcome {
+        against_all(vec![Box::new(send)])
+    }
+
+    // The same with one raw connection per script, each for a seat of its own.
+    fn against_all(scripts: Vec<Script>) -> Outcome {
+        let config = TableConfig {
+            seats: scripts.len() + 1,
+            ..config(100)
+        };
+        let (addrs, raw): (Vec<SocketAddr>, Vec<JoinHandle<()>>) = scripts
+            .into_iter()
+            .map(|send| {
+                let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                let addr = listener.local_addr().unwrap();
+                let raw = thread::spawn(move || {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut connection = Connection::new(stream).unwrap();
+                    let mut player = Player::new();
+                    let deck_key =
+                        Box::new(DeckKey::generate(OsRng).announce(&player.public_key()));
+                    let join = player.sign_message(
+                        &[5; 32],
+                        Message::Join {
+                            name: "Mallory".to_string(),
+                            deck_key,
+                        },
+                    );
+                    connection
+                        .send(&PeerMessage::Hello {
+                            join: Box::new(join),
+                        })
+                        .unwrap();
+                    let _: PeerMessage = connection.receive().unwrap();
+                    send(&mut connection, &mut player);
+                    // Stay on the line until the honest peer hangs up.
+                    while connection.receive::<PeerMessage>().is_ok() {}
+                });
+                (addr, raw)
+            })
+            .unzip();
+        let outcome = play_alice(config, &addrs);
+        for raw in raw {
+            raw.join().unwrap();
+        }
+        outcome
+    }
+
+    fn play_alice(config: TableConfig, dial: &[SocketAddr]) -> Outcome {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut peer = Peer::connect(config, [5; 32], 0, "Alice", Player::new(), &listener, dial)?;
+        peer.set_turn_timeout(Duration::from_secs(1));
+        loop {
+            if let Update::Finished { stacks } = peer.poll(check_or_call)? {
+                return Ok((stacks, *peer.table().state().history()));
+            }
+        }
+    }
+
+    fn signed(player: &mut Player, message: Message) -> PeerMessage {
+        PeerMessage::Signed {
+            message: Box::new(player.sign_message(&[5; 32], message)),
+        }
+    }
+
+    #[test]
+    fn test_messages_two_hands_ahead_are_invalid() {
+        let outcome = against(|connection, player| {
+            // The second commitment could only be for the hand after next.
+            for _ in 0..2 {
+                let commitment = player.commit_contribution();
+                connection
+                    .send(&signed(player, Message::Commit { commitment }))
+                    .unwrap();
+            }
+        });
+        assert!(matches!(
+            outcome,
+            Err(PeerError::Invalid {
+                error: PokerError::OutOfPhase,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_flooding_peer_is_invalid() {
+        // While the table waits on the silent seat's commitment, the flooding seat can be
+        // at most one message ahead, and it sends three.
+        let flood: Script = Box::new(|connection, player| {
+            let commitment = player.commit_contribution();
+            connection
+                .send(&signed(player, Message::Commit { commitment }))
+                .unwrap();
+            for _ in 0..3 {
+                let reveal = Message::Reveal {
+                    contribution: [0; 32],
+                };
+                connection.send(&signed(player, reveal)).unwrap();
+            }
+        });
+        let silent: Script = Box::new(|_, _| {});
+        assert!(matches!(
+            against_all(vec![flood, silent]),
+            Err(PeerError::Invalid {
+                error: PokerError::OutOfPhase,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_silent_peer_times_out() {
+        let outcome = against(|_, _| {});
+        assert!(matches!(outcome, Err(PeerError::TimedOut { seats }) if seats.len() == 1));
+    }
+
+    #[test]
+    fn test_checkpoints_for_unplayed_hands_are_invalid() {
+        let outcome = against(|connection, _| {
+            connection
+                .send(&PeerMessage::Checkpoint {
+                    hand: 1,
+                    history: [0; 32],
+                })
+                .unwrap();
+        });
+        assert!(matches!(
+            outcome,
+            Err(PeerError::Invalid {
+                error: PokerError::OutOfPhase,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_divergence_is_detected() {
+        // One peer starts everyone on a different stack, so its hands settle differently.
+        let results: Vec<_> = run_peers(&[config(100), config(100), config(200)])
+            .into_iter()
+            .map(|peer| peer.join().unwrap())
+            .collect();
+        for result in results {
+            assert!(matches!(result, Err(PeerError::Diverged { hand: 1, .. })));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::card::Card;
+use crate::commit::{commit, Commitment, Contribution};
+use crate::deck::Deck;
+use crate::error::PokerError;
+use crate::keystore::{keypair_from_seed, Keystore, KeystoreError, Seed};
+use crate::transcript::{DrawContext, GameId};
+use crate::verify::CardClaim;
+use crate::wire::{Message, SignedMessage};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use schnorrkel::{
+    vrf::{VRFInOut, VRFProofBatchable},
+    Keypair, PublicKey,
+};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Player {
+    keypair: Keypair,
+    vrf_output: Option<VRFInOut>,
+    vrf_proof: Option<VRFProofBatchable>,
+    // The context of the last draw, so a claim or check for any other one is caught.
+    draw_context: Option<DrawContext>,
+    contribution: Option<Contribution>,
+    // The sequence number of the next message this player sends in each game.
+    sequences: HashMap<GameId, u64>,
+}
+
+impl Player {
+    // A throwaway identity; use `from_seed` or `from_keystore` to keep the same key across sessions.
+    pub fn new() -> Self {
+        Self::generate_with(&mut OsRng)
+    }
+
+    pub fn generate_with(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self::from_keypair(Keypair::generate_with(rng))
+    }
+
+    pub fn from_keypair(keypair: Keypair) -> Self {
+        Player {
+            keypair,
+            vrf_output: None,
+            vrf_proof: None,
+            draw_context: None,
+            contribution: None,
+            sequences: HashMap::new(),
+        }
+    }
+
+    pub fn from_seed(seed: &Seed) -> Self {
+        Self::from_keypair(keypair_from_seed(seed))
+    }
+
+    pub fn from_keystore(keystore: &Keystore, passphrase: &str) -> Result<Self, KeystoreError> {
+        keystore.decrypt(passphrase).map(Self::from_keypair)
+    }
+
+    // Encrypts this player's secret key so it can be saved and loaded in a later session.
+    pub fn export_keystore(&self, passphrase: &str) -> Result<Keystore, KeystoreError> {
+        Keystore::encrypt(&self.keypair, passphrase)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.keypair.public
+    }
+
+    pub fn vrf_output(&self) -> Option<&VRFInOut> {
+        self.vrf_output.as_ref()
+    }
+
+    pub fn vrf_proof(&self) -> Option<&VRFProofBatchable> {
+        self.vrf_proof.as_ref()
+    }
+
+    // Picks a fresh random contribution for the round input and commits to it.
+    pub fn commit_contribution(&mut self) -> Commitment {
+        self.commit_contribution_with(&mut OsRng)
+    }
+
+    pub fn commit_contribution_with(&mut self, rng: &mut (impl RngCore + CryptoRng)) -> Commitment {
+        let mut contribution = [0u8; 32];
+        rng.fill_bytes(&mut contribution);
+        self.contribution = Some(contribution);
+        commit(&self.keypair.public, &contribution)
+    }
+
+    pub fn contribution(&self) -> Option<Contribution> {
+        self.contribution
+    }
+
+    // Signs `message` for `game_id` under this player's next sequence number in that game.
+    pub fn sign_message(&mut self, game_id: &GameId, message: Message) -> SignedMessage {
+        let sequence = self.sequences.entry(*game_id).or_insert(0);
+        let signed = SignedMessage::sign(&self.keypair, *game_id, *sequence, message);
+        *sequence += 1;
+        signed
+    }
+
+    pub fn draw_card(&mut self, context: &DrawContext) {
+        // References https://docs.rs/schnorrkel/latest/schnorrkel/keys/struct.Keypair.html#method.vrf_sign
+        // The VRF generates an output that is deterministic from the input and the private key, but appears random and is not reversible.
+        // The VRF output and a proof that can be used to verify the correctness of the VRF output without revealing the private key.
+        // The transcript binds the game, table, round, card slot and this player's key, so
+        // the proof cannot be reused anywhere else.
+        let (inout, _, proof) = self
+            .keypair
+            .vrf_sign(context.transcript(&self.keypair.public));
+        self.vrf_output = Some(inout);
+        self.vrf_proof = Some(proof);
+        self.draw_context = Some(context.clone());
+    }
+
+    // Reveals the card as if it were the first draw from a full deck.
+    pub fn reveal_card(&self) -> Result<Card, PokerError> {
+        self.reveal_card_from(&mut Deck::new())
+    }
+
+    // Reveals the card selected from the cards still left in `deck` and removes it.
+    pub fn reveal_card_from(&self, deck: &mut Deck) -> Result<Card, PokerError> {
+        let output = self.vrf_output.as_ref().ok_or(PokerError::NotDrawn)?;
+        deck.draw(output).ok_or(PokerError::DeckEmpty)
+    }
+
+    // The public record of the last draw, for anyone to verify against `context`.
+    pub fn claim(&self, context: &DrawContext) -> Result<CardClaim, PokerError> {
+        match (&self.vrf_output, &self.vrf_proof, &self.draw_context) {
+            (Some(output), Some(proof), Some(drawn)) if drawn == context => Ok(CardClaim {
+                public: self.keypair.public,
+                context: context.clone(),
+                output: output.to_preout(),
+                proof: proof.clone(),
+            }),
+            (Some(_), Some(_), Some(_)) => Err(PokerError::InputMismatch),
+            _ => Err(PokerError::NotDrawn),
+        }
+    }
+
+    pub fn verify_card(&self, context: &DrawContext) -> Result<(), PokerError> {
+        self.claim(context)?.verify().map(|_| ())
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::test_context;
+
+    #[test]
+    fn test_new_player() {
+        let player = Player::new();
+        assert!(player.vrf_output.is_none());
+        assert!(player.vrf_proof.is_none());
+    }
+
+    #[test]
+    fn test_identity_survives_keystore() {
+        let mut player = Player::from_seed(&[9u8; 32]);
+        assert_eq!(
+            player.public_key(),
+            Player::from_seed(&[9u8; 32]).public_key()
+        );
+
+        let keystore = player.export_keystore("secret").unwrap();
+        let mut restored = Player::from_keystore(&keystore, "secret").unwrap();
+        assert_eq!(restored.public_key(), player.public_key());
+
+        // Same key, same input: the VRF draw is the same card.
+        player.draw_card(&test_context(b"test"));
+        restored.draw_card(&test_context(b"test"));
+        assert_eq!(player.reveal_card(), restored.reveal_card());
+    }
+
+    #[test]
+    fn test_draw_card() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"test"));
+        assert!(player.vrf_output.is_some());
+        assert!(player.vrf_proof.is_some());
+    }
+
+    #[test]
+    fn test_reveal_card() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"test"));
+        let card = player.reveal_card();
+        assert!(card.is_ok());
+        assert!(card.unwrap().index() < 52);
+    }
+
+    #[test]
+    fn test_not_drawn() {
+        let player = Player::new();
+        assert_eq!(player.reveal_card(), Err(PokerError::NotDrawn));
+        assert_eq!(
+            player.verify_card(&test_context(b"test")),
+            Err(PokerError::NotDrawn)
+        );
+        assert_eq!(
+            player.claim(&test_context(b"test")),
+            Err(PokerError::NotDrawn)
+        );
+    }
+
+    #[test]
+    fn test_verify_card() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"test"));
+        assert_eq!(player.verify_card(&test_context(b"test")), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_card_with_wrong_input() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"test"));
+        assert_eq!(
+            player.verify_card(&test_context(b"wrong")),
+            Err(PokerError::InputMismatch)
+        );
+    }
+
+    #[test]
+    fn test_reveal_card_from_deck() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"test"));
+        let mut deck = Deck::new();
+        let card = player.reveal_card_from(&mut deck).unwrap();
+        assert_eq!(Ok(card), player.reveal_card());
+        assert!(!deck.contains(card));
+        assert_eq!(deck.len(), 51);
+
+        let mut empty = Deck::new();
+        while empty.draw_with(&mut OsRng).is_some() {}
+        assert_eq!(
+            player.reveal_card_from(&mut empty),
+            Err(PokerError::DeckEmpty)
+        );
+    }
+}
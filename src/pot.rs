@@ -0,0 +1,278 @@
+use crate::betting::{Chips, SeatState};
+use crate::hand::HandRank;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pot {
+    pub amount: Chips,
+    // Seats that have not folded and put in enough chips to win this pot.
+    pub eligible: Vec<usize>,
+}
+
+// Splits everything bet in a hand into the main pot followed by any side pots. A new pot
+// starts at every level where a player still in the hand ran out of chips; folded
+// players' chips stay in the pots they contributed to but they are not eligible to win.
+pub fn build_pots(seats: &[SeatState]) -> Vec<Pot> {
+    let mut levels: Vec<Chips> = seats
+        .iter()
+        .filter(|seat| !seat.folded && seat.total_bet > 0)
+        .map(|seat| seat.total_bet)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let mut pots: Vec<Pot> = Vec::new();
+    let mut previous = 0;
+    for &level in &levels {
+        let amount = seats
+            .iter()
+            .map(|seat| seat.total_bet.min(level) - seat.total_bet.min(previous))
+            .sum();
+        let eligible = (0..seats.len())
+            .filter(|&i| !seats[i].folded && seats[i].total_bet >= level)
+            .collect();
+        pots.push(Pot { amount, eligible });
+        previous = level;
+    }
+
+    // Chips folded above the highest live bet go to the last pot.
+    let leftover: Chips = seats
+        .iter()
+        .map(|seat| seat.total_bet.saturating_sub(previous))
+        .sum();
+    if let Some(last) = pots.last_mut() {
+        last.amount += leftover;
+    }
+    pots
+}
+
+// Pays each pot to the best hands among its eligible seats and returns what every seat
+// wins. `ranks` holds each seat's shown hand, or `None` if it has no valid hand; a pot
+// nobody eligible can contest is split among its eligible seats. When a pot does not
+// split evenly the odd chips go one at a time to the winners closest to the left of the
+// dealer.
+pub fn distribute(pots: &[Pot], ranks: &[Option<HandRank>], dealer: usize) -> Vec<Chips> {
+    let n = ranks.len();
+    let mut payouts = vec![0; n];
+    for pot in pots {
+        let best = pot.eligible.iter().filter_map(|&i| ranks[i].as_ref()).max();
+        let mut winners: Vec<usize> = pot
+            .eligible
+            .iter()
+            .copied()
+            .filter(|&i| best.is_none() || ranks[i].as_ref() == best)
+            .collect();
+        if winners.is_empty() {
+            continue;
+        }
+        winners.sort_by_key(|&i| (i + n - dealer - 1) % n);
+
+        let share = pot.amount / winners.len() as Chips;
+        let odd_chips = (pot.amount % winners.len() as Chips) as usize;
+        for (position, &winner) in winners.iter().enumerate() {
+            payouts[winner] += share + if position < odd_chips { 1 } else { 0 };
+        }
+    }
+    payouts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+    use crate::hand::evaluate;
+
+    fn seat(total_bet: Chips, folded: bool) -> SeatState {
+        SeatState {
+            stack: 0,
+            street_bet: 0,
+            total_bet,
+            folded,
+            all_in: false,
+        }
+    }
+
+    fn rank(hand: &str) -> Option<HandRank> {
+        let cards: Vec<Card> = hand
+            .split_whitespace()
+            .map(|c| c.parse().unwrap())
+            .collect();
+        evaluate(&cards).ok()
+    }
+
+    #[test]
+    fn test_single_pot() {
+        let seats = [seat(50, false), seat(50, false), seat(50, false)];
+        assert_eq!(
+            build_pots(&seats),
+            vec![Pot {
+                amount: 150,
+                eligible: vec![0, 1, 2]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_side_pots_for_multiway_all_in() {
+        let seats = [
+            seat(25, false),
+            seat(100, false),
+            seat(60, false),
+            seat(100, false),
+        ];
+        let pots = build_pots(&seats);
+        assert_eq!(
+            pots,
+            vec![
+                Pot {
+                    amount: 100,
+                    eligible: vec![0, 1, 2, 3]
+                },
+                Pot {
+                    amount: 105,
+                    eligible: vec![1, 2, 3]
+                },
+                Pot {
+                    amount: 80,
+                    eligible: vec![1, 3]
+                },
+            ]
+        );
+        assert_eq!(pots.iter().map(|pot| pot.amount).sum::<Chips>(), 285);
+    }
+
+    #[test]
+    fn test_folded_chips_stay_in_pot() {
+        let seats = [seat(40, true), seat(20, false), seat(100, false)];
+        let pots = build_pots(&seats);
+        assert_eq!(
+            pots,
+            vec![
+                Pot {
+                    amount: 60,
+                    eligible: vec![1, 2]
+                },
+                Pot {
+                    amount: 100,
+                    eligible: vec![2]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uncalled_bet_returns_to_bettor() {
+        let seats = [seat(30, false), seat(10, false)];
+        let pots = build_pots(&seats);
+        let payouts = distribute(&pots, &[rank("2c 3d 4h 5s 7c"), rank("As Ad Ah Ac Kd")], 0);
+        assert_eq!(payouts, vec![20, 20]);
+    }
+
+    #[test]
+    fn test_everyone_folds() {
+        let seats = [seat(10, true), seat(5, true), seat(30, false)];
+        let pots = build_pots(&seats);
+        assert_eq!(distribute(&pots, &[None, None, None], 0), vec![0, 0, 45]);
+    }
+
+    #[test]
+    fn test_short_stack_wins_main_pot_only() {
+        let seats = [
+            seat(25, false),
+            seat(100, false),
+            seat(60, false),
+            seat(100, false),
+        ];
+        let ranks = [
+            rank("As Ad Ah Ac Kd"),
+            rank("Ks Kd 2h 3c 4d"),
+            rank("Qs Qd Qh 3c 4d"),
+            rank("2c 3d 4h 5s 7c"),
+        ];
+        let payouts = distribute(&build_pots(&seats), &ranks, 0);
+        assert_eq!(payouts, vec![100, 80, 105, 0]);
+    }
+
+    #[test]
+    fn test_split_pot_with_odd_chip() {
+        let seats = [seat(33, false), seat(33, false), seat(33, true)];
+        let straight = "9s Td Jh Qc Kd";
+        let ranks = [rank(straight), rank(straight), None];
+        // The seat after the dealer gets the odd chip.
+        assert_eq!(distribute(&build_pots(&seats), &ranks, 0), vec![49, 50, 0]);
+        assert_eq!(distribute(&build_pots(&seats), &ranks, 1), vec![50, 49, 0]);
+    }
+
+    #[test]
+    fn test_three_way_split_odd_chips_go_left_of_dealer() {
+        let seats = [
+            seat(34, false),
+            seat(34, false),
+            seat(34, false),
+            seat(34, false),
+        ];
+        let board = "As Ks Qs Js Ts";
+        let ranks = [
+            rank(board),
+            rank(board),
+            rank(board),
+            rank("2c 3d 4h 5s 7c"),
+        ];
+        let payouts = distribute(&build_pots(&seats), &ranks, 1);
+        // 136 chips between three winners: 45 each and one odd chip to seat 2.
+        assert_eq!(payouts, vec![45, 45, 46, 0]);
+    }
+
+    #[test]
+    fn test_side_pot_split_while_main_pot_won() {
+        let seats = [seat(20, false), seat(80, false), seat(80, false)];
+        let flush = "Ah Kh 9h 7h 2h";
+        let ranks = [rank("As Ad Ah Ac Kd"), rank(flush), rank(flush)];
+        let payouts = distribute(&build_pots(&seats), &ranks, 0);
+        assert_eq!(payouts, vec![60, 60, 60]);
+    }
+
+    #[test]
+    fn test_uncontested_pot_without_valid_hands() {
+        let seats = [seat(10, false), seat(10, false)];
+        assert_eq!(
+            distribute(&build_pots(&seats), &[None, None], 0),
+            vec![10, 10]
+        );
+    }
+
+    #[test]
+    fn test_distribution_conserves_chips() {
+        let bets: [Chips; 5] = [7, 13, 13, 50, 101];
+        let hands = [
+            "As Ad Ah Ac Kd",
+            "Ks Kd Kh 3c 3d",
+            "Qs Qd Qh 3c 4d",
+            "2c 3d 4h 5s 7c",
+            "2c 3d 4h 5s 7c",
+        ];
+        for folded_mask in 0u32..32 {
+            for winner_order in 0..5 {
+                let seats: Vec<SeatState> = (0..5)
+                    .map(|i| seat(bets[i], folded_mask & (1 << i) != 0))
+                    .collect();
+                let ranks: Vec<Option<HandRank>> = (0..5)
+                    .map(|i| rank(hands[(i + winner_order) % 5]))
+                    .collect();
+                let pots = build_pots(&seats);
+                let payouts = distribute(&pots, &ranks, winner_order);
+                let total: Chips = bets.iter().sum();
+                if folded_mask == 31 {
+                    assert!(pots.is_empty());
+                } else {
+                    assert_eq!(payouts.iter().sum::<Chips>(), total);
+                    for i in 0..5 {
+                        if seats[i].folded {
+                            assert_eq!(payouts[i], 0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::error::PokerError;
+use crate::transcript::DrawContext;
+use crate::verify::{verify_claims, verify_draw, CardClaim, InvalidClaims};
+use merlin::Transcript;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use schnorrkel::{
+    vrf::{VRFInOut, VRFPreOut, VRFProofBatchable},
+    PublicKey,
+};
+
+pub const JOINT_SEED_LABEL: &[u8] = b"vrf-poker-joint-seed";
+
+// Collects every player's verified VRF output for a round and hashes them into one seed
+// for the cards nobody owns. Outputs are absorbed in public-key order, so the seed is the
+// same for every observer, and no player can predict it before everyone else's output
+// is revealed.
+#[derive(Debug, Clone, Default)]
+pub struct JointSeed {
+    outputs: Vec<(PublicKey, VRFInOut)>,
+}
+
+impl JointSeed {
+    pub fn new() -> Self {
+        JointSeed::default()
+    }
+
+    // Verifies a player's VRF draw under `context` and adds its output. A second output from
+    // the same public key replaces the first.
+    pub fn add(
+        &mut self,
+        public: &PublicKey,
+        context: &DrawContext,
+        output: &VRFPreOut,
+        proof: &VRFProofBatchable,
+    ) -> Result<(), PokerError> {
+        let inout = verify_draw(public, context, output, proof)?;
+        self.insert(*public, inout);
+        Ok(())
+    }
+
+    pub fn add_claim(&mut self, claim: &CardClaim) -> Result<(), PokerError> {
+        self.add(&claim.public, &claim.context, &claim.output, &claim.proof)
+    }
+
+    // Adds every claim after one batched verification. Claims that fail are left out and
+    // their positions returned.
+    pub fn add_claims(&mut self, claims: &[CardClaim]) -> Result<(), InvalidClaims> {
+        match verify_claims(claims) {
+            Ok(outputs) => {
+                for (claim, inout) in claims.iter().zip(outputs) {
+                    self.insert(claim.public, inout);
+                }
+                Ok(())
+            }
+            Err(InvalidClaims(failed)) => {
+                for (i, claim) in claims.iter().enumerate() {
+                    if !failed.contains(&i) {
+                        let _ = self.add_claim(claim);
+                    }
+                }
+                Err(InvalidClaims(failed))
+            }
+        }
+    }
+
+    fn insert(&mut self, public: PublicKey, inout: VRFInOut) {
+        self.outputs.retain(|(key, _)| key != &public);
+        self.outputs.push((public, inout));
+    }
+
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    pub fn seed(&self) -> [u8; 32] {
+        let mut outputs: Vec<&(PublicKey, VRFInOut)> = self.outputs.iter().collect();
+        outputs.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+
+        let mut transcript = Transcript::new(JOINT_SEED_LABEL);
+        for (public, inout) in outputs {
+            transcript.append_message(b"public", public.as_ref());
+            inout.commit(&mut transcript);
+        }
+        let mut seed = [0u8; 32];
+        transcript.challenge_bytes(b"seed", &mut seed);
+        seed
+    }
+
+    pub fn rng(&self) -> ChaChaRng {
+        ChaChaRng::from_seed(self.seed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::transcript::test_context;
+
+    fn draw(player: &mut Player, input: &[u8]) -> (VRFPreOut, VRFProofBatchable) {
+        player.draw_card(&test_context(input));
+        (
+            player.vrf_output().unwrap().to_preout(),
+            player.vrf_proof().unwrap().clone(),
+        )
+    }
+
+    #[test]
+    fn test_seed_ignores_order_added() {
+        let mut players: Vec<Player> = (0..3).map(|_| Player::new()).collect();
+        let draws: Vec<(VRFPreOut, VRFProofBatchable)> = players
+            .iter_mut()
+            .map(|player| draw(player, b"input"))
+            .collect();
+
+        let mut forward = JointSeed::new();
+        let mut backward = JointSeed::new();
+        for i in 0..3 {
+            let (output, proof) = &draws[i];
+            forward
+                .add(
+                    &players[i].public_key(),
+                    &test_context(b"input"),
+                    output,
+                    proof,
+                )
+                .unwrap();
+            let (output, proof) = &draws[2 - i];
+            backward
+                .add(
+                    &players[2 - i].public_key(),
+                    &test_context(b"input"),
+                    output,
+                    proof,
+                )
+                .unwrap();
+        }
+        assert_eq!(forward.len(), 3);
+        assert_eq!(forward.seed(), backward.seed());
+    }
+
+    #[test]
+    fn test_every_output_changes_the_seed() {
+        let mut alice = Player::new();
+        let mut bob = Player::new();
+        let (alice_output, alice_proof) = draw(&mut alice, b"input");
+
+        let mut seeds = Vec::new();
+        for input in [b"input", b"other"] {
+            let (bob_output, bob_proof) = draw(&mut bob, input);
+            let mut joint = JointSeed::new();
+            joint
+                .add(
+                    &alice.public_key(),
+                    &test_context(b"input"),
+                    &alice_output,
+                    &alice_proof,
+                )
+                .unwrap();
+            joint
+                .add(
+                    &bob.public_key(),
+                    &test_context(input),
+                    &bob_output,
+                    &bob_proof,
+                )
+                .unwrap();
+            seeds.push(joint.seed());
+        }
+        assert_ne!(seeds[0], seeds[1]);
+    }
+
+    #[test]
+    fn test_unverified_output_is_rejected() {
+        let mut alice = Player::new();
+        let bob = Player::new();
+        let (output, proof) = draw(&mut alice, b"input");
+        let mut joint = JointSeed::new();
+        assert!(joint
+            .add(
+                &alice.public_key(),
+                &test_context(b"wrong"),
+                &output,
+                &proof
+            )
+            .is_err());
+        assert!(joint
+            .add(&bob.public_key(), &test_context(b"input"), &output, &proof)
+            .is_err());
+        assert!(joint.is_empty());
+    }
+
+    #[test]
+    fn test_same_player_is_counted_once() {
+        let mut alice = Player::new();
+        let (first_output, first_proof) = draw(&mut alice, b"first");
+        let (second_output, second_proof) = draw(&mut alice, b"second");
+
+        let mut joint = JointSeed::new();
+        joint
+            .add(
+                &alice.public_key(),
+                &test_context(b"first"),
+                &first_output,
+                &first_proof,
+            )
+            .unwrap();
+        joint
+            .add(
+                &alice.public_key(),
+                &test_context(b"second"),
+                &second_output,
+                &second_proof,
+            )
+            .unwrap();
+
+        let mut only_second = JointSeed::new();
+        only_second
+            .add(
+                &alice.public_key(),
+                &test_context(b"second"),
+                &second_output,
+                &second_proof,
+            )
+            .unwrap();
+        assert_eq!(joint.len(), 1);
+        assert_eq!(joint.seed(), only_second.seed());
+    }
+
+    #[test]
+    fn test_add_claims_in_one_batch() {
+        let mut players: Vec<Player> = (0..4).map(|_| Player::new()).collect();
+        let mut claims: Vec<CardClaim> = players
+            .iter_mut()
+            .map(|player| {
+                player.draw_card(&test_context(b"input"));
+                player.claim(&test_context(b"input")).unwrap()
+            })
+            .collect();
+
+        let mut one_by_one = JointSeed::new();
+        for claim in &claims {
+            one_by_one.add_claim(claim).unwrap();
+        }
+        let mut batched = JointSeed::new();
+        batched.add_claims(&claims).unwrap();
+        assert_eq!(batched.seed(), one_by_one.seed());
+
+        claims[2].context = test_context(b"wrong");
+        let mut partial = JointSeed::new();
+        assert_eq!(partial.add_claims(&claims), Err(InvalidClaims(vec![2])));
+        assert_eq!(partial.len(), 3);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::mental::{random_permutation, Ciphertext, EncryptedDeck};
+use curve25519_dalek::{
+    ristretto::RistrettoPoint,
+    scalar::Scalar,
+    traits::{Identity, VartimeMultiscalarMul},
+};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+
+pub const SHUFFLE_PROOF_LABEL: &[u8] = b"vrf-poker-shuffle-proof";
+pub const SHUFFLE_GENERATORS_LABEL: &[u8] = b"vrf-poker-shuffle-generators";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleError {
+    KeyMismatch,
+    ContextMismatch,
+    SizeMismatch,
+    InvalidProof,
+    // The pass at this position in a sequence of passes failed to verify.
+    InvalidPass(usize),
+}
+
+impl fmt::Display for ShuffleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShuffleError::KeyMismatch => write!(f, "shuffled deck is under a different key"),
+            ShuffleError::ContextMismatch => write!(f, "shuffled deck is for a different hand"),
+            ShuffleError::SizeMismatch => write!(f, "shuffled deck has a different size"),
+            ShuffleError::InvalidProof => write!(f, "shuffle proof does not verify"),
+            ShuffleError::InvalidPass(pass) => write!(f, "shuffle pass {} does not verify", pass),
+        }
+    }
+}
+
+impl std::error::Error for ShuffleError {}
+
+// Zero-knowledge proof that a shuffled deck holds exactly the cards of the input deck.
+//
+// This is the Terelius-Wikstrom shuffle argument, a linear-size argument in the line of
+// Neff's and Groth's, made non-interactive with Fiat-Shamir. The shuffler commits to its
+// permutation with one Pedersen commitment per card, then proves that the commitments hold
+// a permutation matrix (a chain of commitments shows the product of the challenges is
+// unchanged) and that the output deck is the input deck permuted the same way and
+// re-randomized. Field names follow Haenni et al., "Pseudo-Code Algorithms for Verifiable
+// Re-Encryption Mix-Nets" (2017). For a 52-card deck the proof is 161 points and 108
+// scalars, about 8.6 KB, and proving or checking a pass takes a few hundred scalar
+// multiplications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShuffleProof {
+    // Commitment to the permutation, one point per input card.
+    pub c: Vec<RistrettoPoint>,
+    // Commitment chain over the permuted challenges.
+    pub c_hat: Vec<RistrettoPoint>,
+    pub t1: RistrettoPoint,
+    pub t2: RistrettoPoint,
+    pub t3: RistrettoPoint,
+    // The commitment for the re-encryption, as a ciphertext.
+    pub t4: Ciphertext,
+    pub t_hat: Vec<RistrettoPoint>,
+    pub s1: Scalar,
+    pub s2: Scalar,
+    pub s3: Scalar,
+    pub s4: Scalar,
+    pub s_hat: Vec<Scalar>,
+    pub s_prime: Vec<Scalar>,
+}
+
+// Independent generators h, h_1, ..., h_n with no known discrete logs between them.
+fn generators(n: usize) -> (RistrettoPoint, Vec<RistrettoPoint>) {
+    let mut transcript = Transcript::new(SHUFFLE_GENERATORS_LABEL);
+    let mut points = (0..=n as u64).map(|i| {
+        transcript.append_u64(b"index", i);
+        let mut bytes = [0u8; 64];
+        transcript.challenge_bytes(b"generator", &mut bytes);
+        RistrettoPoint::from_uniform_bytes(&bytes)
+    });
+    let h = points.next().expect("at least one generator");
+    (h, points.collect())
+}
+
+fn append_cards(transcript: &mut Transcript, label: &'static [u8], cards: &[Ciphertext]) {
+    for card in cards {
+        transcript.append_message(label, card.c1.compress().as_bytes());
+        transcript.append_message(label, card.c2.compress().as_bytes());
+    }
+}
+
+fn append_points(transcript: &mut Transcript, label: &'static [u8], points: &[RistrettoPoint]) {
+    for point in points {
+        transcript.append_message(label, point.compress().as_bytes());
+    }
+}
+
+fn challenge_scalar(transcript: &mut Transcript, label: &'static [u8]) -> Scalar {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(label, &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// Starts the transcript with the statement and the permutation commitment, and draws one
+// challenge per input card from it. The statement includes the hand the deck is for, so a
+// pass cannot be replayed in a later hand even if it starts from the same deck.
+fn card_challenges(
+    input: &EncryptedDeck,
+    output: &EncryptedDeck,
+    c: &[RistrettoPoint],
+) -> (Transcript, Vec<Scalar>) {
+    let mut transcript = Transcript::new(SHUFFLE_PROOF_LABEL);
+    input.context().append_to(&mut transcript);
+    transcript.append_message(b"joint-key", input.joint_key().compress().as_bytes());
+    append_cards(&mut transcript, b"input", input.cards());
+    append_cards(&mut transcript, b"output", output.cards());
+    append_points(&mut transcript, b"c", c);
+    let u = (0..c.len())
+        .map(|_| challenge_scalar(&mut transcript, b"u"))
+        .collect();
+    (transcript, u)
+}
+
+// The challenge for the responses, once the commitment chain and every `t` are in.
+fn response_challenge(mut transcript: Transcript, proof: &ShuffleProof) -> Scalar {
+    append_points(&mut transcript, b"c-hat", &proof.c_hat);
+    append_points(&mut transcript, b"t", &[proof.t1, proof.t2, proof.t3]);
+    append_cards(&mut transcript, b"t4", &[proof.t4]);
+    append_points(&mut transcript, b"t-hat", &proof.t_hat);
+    challenge_scalar(&mut transcript, b"challenge")
+}
+
+// Shuffles the deck and proves the shuffle was honest.
+pub fn prove_shuffle<R: RngCore + CryptoRng>(
+    input: &EncryptedDeck,
+    rng: &mut R,
+) -> (EncryptedDeck, ShuffleProof) {
+    let n = input.cards().len();
+    let random_scalars = |rng: &mut R| (0..n).map(|_| Scalar::random(rng)).collect::<Vec<Scalar>>();
+    let g = RistrettoPoint::mul_base;
+    let pk = *input.joint_key();
+
+    // Output card i is input card `permutation[i]` re-randomized with `randomness[i]`.
+    let permutation = random_permutation(n, rng);
+    let randomness = random_scalars(rng);
+    let output = input.permute(&permutation, &randomness);
+    let (h, hs) = generators(n);
+
+    // Commit to the permutation: the commitment for input card j hides h_i for the output
+    // position i it went to.
+    let r = random_scalars(rng);
+    let mut c = vec![RistrettoPoint::identity(); n];
+    for (i, &j) in permutation.iter().enumerate() {
+        c[j] = g(&r[j]) + hs[i];
+    }
+    let (transcript, u) = card_challenges(input, &output, &c);
+    let u_tilde: Vec<Scalar> = permutation.iter().map(|&j| u[j]).collect();
+
+    // Chain the permuted challenges: c_hat_i = g^r_hat_i * c_hat_{i-1}^u_tilde_i.
+    let r_hat = random_scalars(rng);
+    let mut c_hat = Vec::with_capacity(n);
+    let mut previous = h;
+    for i in 0..n {
+        previous = g(&r_hat[i]) + previous * u_tilde[i];
+        c_hat.push(previous);
+    }
+
+    let (w1, w2, w3, w4) = (
+        Scalar::random(rng),
+        Scalar::random(rng),
+        Scalar::random(rng),
+        Scalar::random(rng),
+    );
+    let w_hat = random_scalars(rng);
+    let w_prime = random_scalars(rng);
+    let weighted = |points: &mut dyn Iterator<Item = RistrettoPoint>| -> RistrettoPoint {
+        points.zip(&w_prime).map(|(point, w)| point * w).sum()
+    };
+    let mut proof = ShuffleProof {
+        t1: g(&w1),
+        t2: g(&w2),
+        t3: g(&w3) + weighted(&mut hs.iter().copied()),
+        t4: Ciphertext {
+            c1: weighted(&mut output.cards().iter().map(|card| card.c1)) - g(&w4),
+            c2: weighted(&mut output.cards().iter().map(|card| card.c2)) - pk * w4,
+        },
+        t_hat: (0..n)
+            .map(|i| {
+                let previous = if i == 0 { h } else { c_hat[i - 1] };
+                g(&w_hat[i]) + previous * w_prime[i]
+            })
+            .collect(),
+        c,
+        c_hat,
+        s1: Scalar::ZERO,
+        s2: Scalar::ZERO,
+        s3: Scalar::ZERO,
+        s4: Scalar::ZERO,
+        s_hat: Vec::new(),
+        s_prime: Vec::new(),
+    };
+    let e = response_challenge(transcript, &proof);
+
+    // v_i is the product of the permuted challenges after position i.
+    let mut v = vec![Scalar::ONE; n];
+    for i in (0..n.saturating_sub(1)).rev() {
+        v[i] = v[i + 1] * u_tilde[i + 1];
+    }
+    let r_bar: Scalar = r.iter().sum();
+    let r_hat_sum: Scalar = r_hat.iter().zip(&v).map(|(r, v)| r * v).sum();
+    let r_tilde: Scalar = r.iter().zip(&u).map(|(r, u)| r * u).sum();
+    let r_prime: Scalar = randomness.iter().zip(&u_tilde).map(|(r, u)| r * u).sum();
+    proof.s1 = w1 - e * r_bar;
+    proof.s2 = w2 - e * r_hat_sum;
+    proof.s3 = w3 - e * r_tilde;
+    proof.s4 = w4 - e * r_prime;
+    proof.s_hat = (0..n).map(|i| w_hat[i] - e * r_hat[i]).collect();
+    proof.s_prime = (0..n).map(|i| w_prime[i] - e * u_tilde[i]).collect();
+    (output, proof)
+}
+
+impl ShuffleProof {
+    pub fn verify(
+        &self,
+        input: &EncryptedDeck,
+        output: &EncryptedDeck,
+    ) -> Result<(), ShuffleError> {
+        if input.joint_key() != output.joint_key() {
+            return Err(ShuffleError::KeyMismatch);
+        }
+        if input.context() != output.context() {
+            return Err(ShuffleError::ContextMismatch);
+        }
+        let n = input.cards().len();
+        if output.cards().len() != n {
+            return Err(ShuffleError::SizeMismatch);
+        }
+        if n == 0
+            || [&self.c, &self.c_hat, &self.t_hat]
+                .iter()
+                .any(|points| points.len() != n)
+            || self.s_hat.len() != n
+            || self.s_prime.len() != n
+        {
+            return Err(ShuffleError::InvalidProof);
+        }
+
+        let g = RistrettoPoint::mul_base;
+        let pk = *input.joint_key();
+        let (h, hs) = generators(n);
+        let (transcript, u) = card_challenges(input, output, &self.c);
+        let e = response_challenge(transcript, self);
+        let combine = |scalars: &[Scalar], points: &mut dyn Iterator<Item = RistrettoPoint>| {
+            RistrettoPoint::vartime_multiscalar_mul(scalars, points)
+        };
+
+        let c_bar: RistrettoPoint =
+            self.c.iter().sum::<RistrettoPoint>() - hs.iter().sum::<RistrettoPoint>();
+        let u_product: Scalar = u.iter().product();
+        let c_hat_last = self.c_hat[n - 1] - h * u_product;
+        let c_tilde = combine(&u, &mut self.c.iter().copied());
+        let a_prime = combine(&u, &mut input.cards().iter().map(|card| card.c2));
+        let b_prime = combine(&u, &mut input.cards().iter().map(|card| card.c1));
+
+        let t1 = c_bar * e + g(&self.s1);
+        let t2 = c_hat_last * e + g(&self.s2);
+        let t3 = c_tilde * e + g(&self.s3) + combine(&self.s_prime, &mut hs.iter().copied());
+        let t4 = Ciphertext {
+            c1: b_prime * e - g(&self.s4)
+                + combine(
+                    &self.s_prime,
+                    &mut output.cards().iter().map(|card| card.c1),
+                ),
+            c2: a_prime * e - pk * self.s4
+                + combine(
+                    &self.s_prime,
+                    &mut output.cards().iter().map(|card| card.c2),
+                ),
+        };
+        let chain_holds = (0..n).all(|i| {
+            let previous = if i == 0 { h } else { self.c_hat[i - 1] };
+            self.c_hat[i] * e + g(&self.s_hat[i]) + previous * self.s_prime[i] == self.t_hat[i]
+        });
+
+        if chain_holds && t1 == self.t1 && t2 == self.t2 && t3 == self.t3 && t4 == self.t4 {
+            Ok(())
+        } else {
+            Err(ShuffleError::InvalidProof)
+        }
+    }
+}
+
+// Checks a sequence of shuffle passes starting from `initial` and returns the final deck.
+// Each pass must be verified before the next player shuffles on top of it.
+pub fn verify_passes<'a>(
+    initial: &'a EncryptedDeck,
+    passes: &'a [(EncryptedDeck, ShuffleProof)],
+) -> Result<&'a EncryptedDeck, ShuffleError> {
+    let mut deck = initial;
+    for (pass, (output, proof)) in passes.iter().enumerate() {
+        proof
+            .verify(deck, output)
+            .map_err(|_| ShuffleError::InvalidPass(pass))?;
+        deck = output;
+    }
+    Ok(deck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+    use crate::mental::{announced, DeckKey};
+    use crate::transcript::{test_deck_context, DeckContext};
+    use rand::rngs::OsRng;
+
+    fn table(n: usize) -> (Vec<DeckKey>, EncryptedDeck) {
+        let keys: Vec<DeckKey> = (0..n).map(|_| DeckKey::generate(OsRng)).collect();
+        let deck = EncryptedDeck::new(test_deck_context(), announced(&keys));
+        (keys, deck)
+    }
+
+    #[test]
+    fn test_honest_shuffle_verifies() {
+        let (_, deck) = table(2);
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+        assert!(proof.verify(&deck, &shuffled).is_ok());
+        assert_ne!(shuffled, deck);
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_decks() {
+        let (_, deck) = table(2);
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+        let (other, _) = prove_shuffle(&deck, &mut OsRng);
+        assert_eq!(proof.verify(&deck, &other), Err(ShuffleError::InvalidProof));
+        assert_eq!(
+            proof.verify(&other, &shuffled),
+            Err(ShuffleError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_the_hand() {
+        // The same open deck under the same key, dealt again the next hand.
+        let (_, deck) = table(2);
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+        let next_hand = DeckContext {
+            hand: deck.context().hand + 1,
+            ..deck.context().clone()
+        };
+        let replayed = (
+            EncryptedDeck::from_cards(next_hand.clone(), *deck.joint_key(), deck.cards().to_vec()),
+            EncryptedDeck::from_cards(next_hand, *deck.joint_key(), shuffled.cards().to_vec()),
+        );
+        assert_eq!(
+            proof.verify(&replayed.0, &replayed.1),
+            Err(ShuffleError::InvalidProof)
+        );
+        assert_eq!(
+            proof.verify(&deck, &replayed.1),
+            Err(ShuffleError::ContextMismatch)
+        );
+    }
+
+    #[test]
+    fn test_duplicated_card_is_caught() {
+        let (_, deck) = table(2);
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+        let mut cards = shuffled.cards().to_vec();
+        cards[1] = cards[0].rerandomize(deck.joint_key(), &Scalar::from(7u64));
+        let cheat = EncryptedDeck::from_cards(test_deck_context(), *deck.joint_key(), cards);
+        assert_eq!(proof.verify(&deck, &cheat), Err(ShuffleError::InvalidProof));
+    }
+
+    #[test]
+    fn test_cheating_prover_is_caught() {
+        // Swapping in a card of the shuffler's choosing breaks the proof, however the
+        // permutation commitments are rearranged.
+        let (_, deck) = table(2);
+        let (shuffled, mut proof) = prove_shuffle(&deck, &mut OsRng);
+        let mut cards = shuffled.cards().to_vec();
+        let ace = Ciphertext::open(Card::from_index(51).unwrap());
+        cards[0] = ace.rerandomize(deck.joint_key(), &Scalar::from(3u64));
+        let cheat = EncryptedDeck::from_cards(test_deck_context(), *deck.joint_key(), cards);
+        proof.c.swap(0, 1);
+        assert!(proof.verify(&deck, &cheat).is_err());
+    }
+
+    #[test]
+    fn test_malformed_proofs() {
+        let (_, deck) = table(2);
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+
+        let mut short = proof.clone();
+        short.c_hat.pop();
+        assert_eq!(
+            short.verify(&deck, &shuffled),
+            Err(ShuffleError::InvalidProof)
+        );
+
+        let mut wrong_response = proof.clone();
+        wrong_response.s_prime[0] += Scalar::ONE;
+        assert_eq!(
+            wrong_response.verify(&deck, &shuffled),
+            Err(ShuffleError::InvalidProof)
+        );
+
+        let (_, other_table) = table(2);
+        assert_eq!(
+            proof.verify(&other_table, &shuffled),
+            Err(ShuffleError::KeyMismatch)
+        );
+
+        let truncated = EncryptedDeck::from_cards(
+            test_deck_context(),
+            *deck.joint_key(),
+            shuffled.cards()[1..].to_vec(),
+        );
+        assert_eq!(
+            proof.verify(&deck, &truncated),
+            Err(ShuffleError::SizeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_every_player_shuffles_and_checks() {
+        let (mut keys, deck) = table(3);
+        let mut passes: Vec<(EncryptedDeck, ShuffleProof)> = Vec::new();
+        for _ in &keys {
+            let current = verify_passes(&deck, &passes).unwrap().clone();
+            passes.push(prove_shuffle(&current, &mut OsRng));
+        }
+        let shuffled = verify_passes(&deck, &passes).unwrap();
+
+        // Decrypting the final deck still gives every card exactly once.
+        for key in keys.iter_mut() {
+            key.use_deck(test_deck_context(), *deck.joint_key(), &passes)
+                .unwrap();
+        }
+        let mut cards: Vec<Card> = shuffled
+            .cards()
+            .iter()
+            .enumerate()
+            .map(|(position, ciphertext)| {
+                let shares: Vec<RistrettoPoint> = keys
+                    .iter()
+                    .map(|key| key.decryption_share(position).unwrap().share)
+                    .collect();
+                ciphertext.decrypt(&shares).unwrap()
+            })
+            .collect();
+        cards.sort_by_key(|card| card.index());
+        assert_eq!(
+            cards,
+            (0..52).filter_map(Card::from_index).collect::<Vec<_>>()
+        );
+
+        let mut tampered = passes.clone();
+        tampered.swap(1, 2);
+        assert_eq!(
+            verify_passes(&deck, &tampered),
+            Err(ShuffleError::InvalidPass(1))
+        );
+    }
+}
@@ -0,0 +1,548 @@
+use crate::beacon::{CommitRevealBeacon, RandomnessBeacon};
+use crate::betting::{Action, Chips};
+use crate::engine::{Effect, Event, GameState};
+use crate::error::PokerError;
+use crate::inbox::Inbox;
+use crate::mental::{DeckKey, DecryptionShare, KeyAnnouncement};
+use crate::player::Player;
+use crate::shuffle::prove_shuffle;
+use crate::transcript::{GameId, TableId};
+use crate::wire::{Message, SignedMessage};
+use rand::rngs::OsRng;
+use schnorrkel::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// What everyone at a table has to agree on before the first hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableConfig {
+    pub seats: usize,
+    pub stack: Chips,
+    pub small_blind: Chips,
+    pub big_blind: Chips,
+    // The table closes after this many hands, or once somebody is out of chips.
+    pub max_hands: Option<u32>,
+}
+
+// The next thing a seat owes the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    Commit,
+    Reveal,
+    Shuffle,
+    // Decryption shares for these deck positions.
+    Shares(Vec<usize>),
+    Act,
+}
+
+impl Expected {
+    // `player`'s message for anything but a betting decision, which is theirs to make.
+    // `key` is the deck key the player announced when joining `state`'s table.
+    pub fn answer(
+        &self,
+        state: &GameState,
+        player: &mut Player,
+        key: &mut DeckKey,
+    ) -> Option<Message> {
+        match self {
+            Expected::Commit => Some(Message::Commit {
+                commitment: player.commit_contribution(),
+            }),
+            Expected::Reveal => player
+                .contribution()
+                .map(|contribution| Message::Reveal { contribution }),
+            Expected::Shuffle => {
+                let (deck, proof) = prove_shuffle(state.deck()?, &mut OsRng);
+                Some(Message::Shuffle {
+                    deck: deck.cards().to_vec(),
+                    proof: Box::new(proof),
+                })
+            }
+            Expected::Shares(positions) => {
+                state.prepare_key(key);
+                let shares = positions
+                    .iter()
+                    .map(|&position| key.decryption_share(position))
+                    .collect::<Option<Vec<DecryptionShare>>>()?;
+                Some(Message::Shares { shares })
+            }
+            Expected::Act => None,
+        }
+    }
+}
+
+// The players, deck keys and inbox that a set of joins seats.
+struct Seating {
+    players: Vec<(String, PublicKey)>,
+    deck_keys: Vec<KeyAnnouncement>,
+    inbox: Inbox,
+}
+
+// One table's complete state, built only from the players' signed messages: their joins,
+// then commit-reveal for each hand's input, shuffles, decryption shares and betting
+// actions. The input orders the hand's starting deck and is bound into every shuffle and
+// share of the hand. Anyone holding the same messages in the same order, whether the host
+// or a player, ends up with the same table, so every participant can check every proof
+// and every action for themselves.
+#[derive(Debug, Clone)]
+pub struct Table {
+    config: TableConfig,
+    inbox: Inbox,
+    beacon: CommitRevealBeacon,
+    state: GameState,
+}
+
+impl Table {
+    // Seats the players whose joins these are, in order. Each join must be the signer's
+    // first message in this game.
+    pub fn from_joins(
+        config: TableConfig,
+        game_id: GameId,
+        table_id: TableId,
+        joins: &[SignedMessage],
+    ) -> Result<Self, PokerError> {
+        if joins.len() != config.seats {
+            return Err(PokerError::InvalidJoin);
+        }
+        let Seating {
+            players,
+            deck_keys,
+            inbox,
+        } = Self::seat(game_id, joins)?;
+        let publics: Vec<PublicKey> = players.iter().map(|(_, public)| *public).collect();
+        let state = GameState::new(
+            game_id,
+            table_id,
+            players,
+            &deck_keys,
+            vec![config.stack; config.seats],
+            (config.small_blind, config.big_blind),
+        )?;
+        Ok(Table {
+            config,
+            inbox,
+            beacon: CommitRevealBeacon::new(publics)?,
+            state,
+        })
+    }
+
+    // Checks joins the way `from_joins` does, but without them having to fill a table yet,
+    // e.g. while players are still arriving.
+    pub fn check_joins(game_id: GameId, joins: &[SignedMessage]) -> Result<(), PokerError> {
+        Self::seat(game_id, joins).map(|_| ())
+    }
+
+    fn seat(game_id: GameId, joins: &[SignedMessage]) -> Result<Seating, PokerError> {
+        let mut players: Vec<(String, PublicKey)> = Vec::new();
+        let mut deck_keys = Vec::new();
+        for join in joins {
+            let Message::Join { name, deck_key } = &join.message else {
+                return Err(PokerError::InvalidJoin);
+            };
+            if players.iter().any(|(_, public)| public == &join.public) {
+                return Err(PokerError::InvalidJoin);
+            }
+            if !deck_key.verify(&join.public) {
+                return Err(PokerError::InvalidJoin);
+            }
+            players.push((name.clone(), join.public));
+            deck_keys.push((**deck_key).clone());
+        }
+
+        let publics: Vec<PublicKey> = players.iter().map(|(_, public)| *public).collect();
+        let mut inbox = Inbox::new(game_id, publics);
+        for join in joins {
+            inbox.receive(join)?;
+        }
+        Ok(Seating {
+            players,
+            deck_keys,
+            inbox,
+        })
+    }
+
+    pub fn config(&self) -> &TableConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn seat_of(&self, public: &PublicKey) -> Option<usize> {
+        self.state
+            .players()
+            .iter()
+            .position(|(_, seated)| seated == public)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_idle()
+            && (self.state.stacks().contains(&0)
+                || self
+                    .config
+                    .max_hands
+                    .is_some_and(|max| self.state.hand_number() >= max))
+    }
+
+    pub fn expected(&self, seat: usize) -> Option<Expected> {
+        if self.is_finished() {
+            return None;
+        }
+        if self.state.is_idle() {
+            let session = self.beacon.session();
+            let owes = if session.all_committed() {
+                session
+                    .missing_reveals()
+                    .contains(&seat)
+                    .then_some(Expected::Reveal)
+            } else {
+                session
+                    .missing_commitments()
+                    .contains(&seat)
+                    .then_some(Expected::Commit)
+            };
+            return owes;
+        }
+        if let Some(shuffler) = self.state.shuffler() {
+            return (shuffler == seat).then_some(Expected::Shuffle);
+        }
+        let owed = self.state.owed_shares(seat);
+        if !owed.is_empty() {
+            return Some(Expected::Shares(owed));
+        }
+        (self.state.to_act() == Some(seat)).then_some(Expected::Act)
+    }
+
+    // Checks and applies the next message from a player, returning their seat and what it
+    // caused. A rejected message changes nothing, not even the sender's sequence number.
+    pub fn receive(&mut self, signed: &SignedMessage) -> Result<(usize, Vec<Effect>), PokerError> {
+        let mut next = self.clone();
+        let (seat, message) = next.inbox.receive(signed)?;
+        let effects = next.handle(seat, message)?;
+        *self = next;
+        Ok((seat, effects))
+    }
+
+    // Gives up waiting on `seats`, in increasing order, which must all owe the table
+    // something. A seat that should act folds; any other staller is fined its whole buy-in,
+    // which calls off the hand in progress and starts the commit-reveal over. Whoever runs
+    // the table calls this when a turn deadline passes or a player leaves; replicas apply it
+    // at the same point in the message order.
+    pub fn time_out(&mut self, seats: &[usize]) -> Result<Vec<Effect>, PokerError> {
+        if self.is_finished() {
+            return Err(PokerError::GameOver);
+        }
+        let owed: Vec<Option<Expected>> = seats.iter().map(|&seat| self.expected(seat)).collect();
+        if owed.is_empty() || owed.contains(&None) || seats.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(PokerError::OutOfPhase);
+        }
+        if owed == [Some(Expected::Act)] {
+            return self.state.apply(Event::Act {
+                seat: seats[0],
+                action: Action::Fold,
+            });
+        }
+        let effects = self.state.apply(Event::Forfeit {
+            seats: seats.to_vec(),
+            penalty: self.config.stack,
+        })?;
+        self.beacon.reset();
+        Ok(effects)
+    }
+
+    fn handle(&mut self, seat: usize, message: &Message) -> Result<Vec<Effect>, PokerError> {
+        if self.is_finished() {
+            return Err(PokerError::GameOver);
+        }
+        let public = self.state.players()[seat].1;
+        match message {
+            Message::Join { .. } => Err(PokerError::OutOfPhase),
+            Message::Commit { commitment } => {
+                if !self.state.is_idle() {
+                    return Err(PokerError::OutOfPhase);
+                }
+                self.beacon.commit(&public, *commitment)?;
+                Ok(Vec::new())
+            }
+            Message::Reveal { contribution } => {
+                if !self.state.is_idle() {
+                    return Err(PokerError::OutOfPhase);
+                }
+                self.beacon.reveal(&public, *contribution)?;
+                if !self.beacon.session().missing_reveals().is_empty() {
+                    return Ok(Vec::new());
+                }
+                let input = self.beacon.round_input(self.state.hand_number() + 1)?;
+                self.state.apply(Event::StartHand {
+                    input: input.to_vec(),
+                })
+            }
+            Message::Shuffle { deck, proof } => self.state.apply(Event::Shuffle {
+                seat,
+                deck: deck.clone(),
+                proof: proof.clone(),
+            }),
+            Message::Shares { shares } => self.state.apply(Event::Shares {
+                seat,
+                shares: shares.clone(),
+            }),
+            Message::Action { action } => self.state.apply(Event::Act {
+                seat,
+                action: *action,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::CommitError;
+
+    const GAME: GameId = [6; 32];
+
+    fn config(seats: usize) -> TableConfig {
+        TableConfig {
+            seats,
+            stack: 100,
+            small_blind: 5,
+            big_blind: 10,
+            max_hands: Some(2),
+        }
+    }
+
+    struct Seat {
+        player: Player,
+        key: DeckKey,
+    }
+
+    fn seat(seats: usize) -> (Vec<Seat>, Vec<SignedMessage>) {
+        let mut seated: Vec<Seat> = (0..seats as u8)
+            .map(|i| Seat {
+                player: Player::from_seed(&[i; 32]),
+                key: DeckKey::generate(OsRng),
+            })
+            .collect();
+        let joins = seated
+            .iter_mut()
+            .enumerate()
+            .map(|(i, seat)| {
+                let deck_key = Box::new(seat.key.announce(&seat.player.public_key()));
+                seat.player.sign_message(
+                    &GAME,
+                    Message::Join {
+                        name: format!("Player {}", i),
+                        deck_key,
+                    },
+                )
+            })
+            .collect();
+        (seated, joins)
+    }
+
+    impl Seat {
+        fn answer(&mut self, table: &Table, expected: &Expected) -> SignedMessage {
+            let message = expected
+                .answer(table.state(), &mut self.player, &mut self.key)
+                .unwrap();
+            self.player.sign_message(&GAME, message)
+        }
+    }
+
+    // Plays every seat honestly, checking or calling, and returns everything that was sent.
+    fn play(table: &mut Table, seated: &mut [Seat]) -> Vec<SignedMessage> {
+        let mut sent = Vec::new();
+        while !table.is_finished() {
+            let (seat, expected) = (0..seated.len())
+                .find_map(|seat| table.expected(seat).map(|expected| (seat, expected)))
+                .expect("somebody always owes the table a message");
+            let signed = if expected == Expected::Act {
+                let betting = table.state().betting().unwrap();
+                let action = if betting.amount_to_call(seat) == 0 {
+                    Action::Check
+                } else {
+                    Action::Call
+                };
+                seated[seat]
+                    .player
+                    .sign_message(&GAME, Message::Action { action })
+            } else {
+                seated[seat].answer(table, &expected)
+            };
+            table.receive(&signed).unwrap();
+            sent.push(signed);
+        }
+        sent
+    }
+
+    #[test]
+    fn test_replicas_agree() {
+        let (mut seated, joins) = seat(3);
+        let mut host = Table::from_joins(config(3), GAME, 0, &joins).unwrap();
+        let sent = play(&mut host, &mut seated);
+        assert_eq!(host.state().hand_number(), 2);
+        assert_eq!(host.state().stacks().iter().sum::<Chips>(), 300);
+
+        let mut replica = Table::from_joins(config(3), GAME, 0, &joins).unwrap();
+        for signed in &sent {
+            replica.receive(signed).unwrap();
+        }
+        assert!(replica.is_finished());
+        assert_eq!(replica.state().stacks(), host.state().stacks());
+        assert_eq!(replica.state().dealer(), host.state().dealer());
+    }
+
+    #[test]
+    fn test_rejects_out_of_turn_messages() {
+        let (mut seated, joins) = seat(2);
+        let mut table = Table::from_joins(config(2), GAME, 0, &joins).unwrap();
+        assert_eq!(table.expected(0), Some(Expected::Commit));
+
+        // Nobody may reveal or act before everyone has committed.
+        let contribution = [1; 32];
+        let reveal = seated[0]
+            .player
+            .sign_message(&GAME, Message::Reveal { contribution });
+        assert_eq!(
+            table.receive(&reveal),
+            Err(PokerError::Commit(CommitError::CommitPhaseOpen))
+        );
+        // The rejected message did not use up its sequence number.
+        assert_eq!(
+            table.receive(&reveal),
+            Err(PokerError::Commit(CommitError::CommitPhaseOpen))
+        );
+        let act = seated[1].player.sign_message(
+            &GAME,
+            Message::Action {
+                action: Action::Call,
+            },
+        );
+        assert_eq!(table.receive(&act), Err(PokerError::OutOfPhase));
+        assert_eq!(table.expected(1), Some(Expected::Commit));
+    }
+
+    #[test]
+    fn test_missing_reveals_are_forfeited() {
+        let (mut seated, joins) = seat(2);
+        let mut table = Table::from_joins(config(2), GAME, 0, &joins).unwrap();
+        for seat in seated.iter_mut() {
+            let commit = seat.answer(&table, &Expected::Commit);
+            table.receive(&commit).unwrap();
+        }
+        let reveal = seated[0].answer(&table, &Expected::Reveal);
+        table.receive(&reveal).unwrap();
+        assert_eq!(table.expected(1), Some(Expected::Reveal));
+
+        // Only a seat that owes something can be timed out.
+        assert_eq!(table.time_out(&[0, 1]), Err(PokerError::OutOfPhase));
+        let effects = table.time_out(&[1]).unwrap();
+        assert_eq!(
+            effects,
+            vec![Effect::Forfeited {
+                seats: vec![1],
+                stacks: vec![200, 0],
+            }]
+        );
+        assert!(table.is_finished());
+        assert_eq!(table.time_out(&[1]), Err(PokerError::GameOver));
+    }
+
+    #[test]
+    fn test_stalled_turns_are_timed_out() {
+        let (mut seated, joins) = seat(3);
+        let mut table = Table::from_joins(config(3), GAME, 0, &joins).unwrap();
+        assert_eq!(table.time_out(&[]), Err(PokerError::OutOfPhase));
+        assert_eq!(table.time_out(&[1, 1]), Err(PokerError::OutOfPhase));
+        for expected in [Expected::Commit, Expected::Reveal] {
+            for seat in seated.iter_mut() {
+                let signed = seat.answer(&table, &expected);
+                table.receive(&signed).unwrap();
+            }
+        }
+        while let Some(Expected::Shuffle) = table.expected(0) {
+            let signed = seated[0].answer(&table, &Expected::Shuffle);
+            table.receive(&signed).unwrap();
+        }
+
+        // Seat 1 should shuffle next. Timing it out calls the hand off and fines it.
+        assert_eq!(table.expected(1), Some(Expected::Shuffle));
+        let effects = table.time_out(&[1]).unwrap();
+        assert_eq!(
+            effects,
+            vec![Effect::Forfeited {
+                seats: vec![1],
+                stacks: vec![150, 0, 150],
+            }]
+        );
+        assert!(table.state().is_idle());
+        assert!(table.is_finished());
+    }
+
+    #[test]
+    fn test_timed_out_actor_folds() {
+        let (mut seated, joins) = seat(2);
+        let mut table = Table::from_joins(config(2), GAME, 0, &joins).unwrap();
+        let seat = loop {
+            let (seat, expected) = (0..2)
+                .find_map(|seat| table.expected(seat).map(|expected| (seat, expected)))
+                .unwrap();
+            if expected == Expected::Act {
+                break seat;
+            }
+            let signed = seated[seat].answer(&table, &expected);
+            table.receive(&signed).unwrap();
+        };
+        let effects = table.time_out(&[seat]).unwrap();
+        assert_eq!(
+            effects[0],
+            Effect::Acted {
+                seat,
+                action: Action::Fold
+            }
+        );
+        assert_eq!(table.state().hand_number(), 1);
+        assert!(table.state().is_idle());
+        assert_eq!(table.expected(seat), Some(Expected::Commit));
+    }
+
+    #[test]
+    fn test_joins_must_seat_the_table() {
+        let (_, joins) = seat(2);
+        assert_eq!(
+            Table::from_joins(config(3), GAME, 0, &joins).err(),
+            Some(PokerError::InvalidJoin)
+        );
+        let twice = [joins[0].clone(), joins[0].clone()];
+        assert_eq!(
+            Table::from_joins(config(2), GAME, 0, &twice).err(),
+            Some(PokerError::InvalidJoin)
+        );
+        assert_eq!(
+            Table::check_joins(GAME, &twice),
+            Err(PokerError::InvalidJoin)
+        );
+        assert_eq!(Table::check_joins(GAME, &joins[..1]), Ok(()));
+        // A table of one cannot play.
+        assert_eq!(
+            Table::from_joins(config(1), GAME, 0, &joins[..1]).err(),
+            Some(PokerError::TooFewPlayers)
+        );
+
+        // A deck key only seats the player it was announced for.
+        let Message::Join { deck_key, .. } = &joins[0].message else {
+            unreachable!()
+        };
+        let copied = Player::new().sign_message(
+            &GAME,
+            Message::Join {
+                name: "Mallory".to_string(),
+                deck_key: deck_key.clone(),
+            },
+        );
+        assert_eq!(
+            Table::check_joins(GAME, &[joins[0].clone(), copied]),
+            Err(PokerError::InvalidJoin)
+        );
+    }
+}
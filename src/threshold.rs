@@ -0,0 +1,495 @@
+use crate::card::Card;
+use crate::mental::{decode_card, Ciphertext};
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar, traits::Identity};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+
+pub const CHAUM_PEDERSEN_LABEL: &[u8] = b"vrf-poker-chaum-pedersen";
+
+// Players are numbered from 1 in threshold sharing, since the secret sits at x = 0.
+pub type ShareIndex = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThresholdError {
+    // A threshold of zero, or more than the number of players.
+    InvalidThreshold { threshold: usize, players: usize },
+    // The dealing from this seat does not match its commitments.
+    InvalidDealing(usize),
+    InvalidShare(ShareIndex),
+    DuplicateShare(ShareIndex),
+    NotEnoughShares { have: usize, need: usize },
+    NotACard,
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdError::InvalidThreshold { threshold, players } => {
+                write!(
+                    f,
+                    "threshold {} out of range for {} players",
+                    threshold, players
+                )
+            }
+            ThresholdError::InvalidDealing(seat) => write!(f, "invalid dealing from seat {}", seat),
+            ThresholdError::InvalidShare(index) => write!(f, "invalid decryption share {}", index),
+            ThresholdError::DuplicateShare(index) => {
+                write!(f, "duplicate decryption share {}", index)
+            }
+            ThresholdError::NotEnoughShares { have, need } => {
+                write!(f, "{} decryption shares, {} needed", have, need)
+            }
+            ThresholdError::NotACard => write!(f, "ciphertext does not decrypt to a card"),
+        }
+    }
+}
+
+impl std::error::Error for ThresholdError {}
+
+fn challenge_scalar(transcript: &mut Transcript) -> Scalar {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// Proof that log_G(X) = log_H(Y) without revealing the logarithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaumPedersenProof {
+    pub c: Scalar,
+    pub s: Scalar,
+}
+
+fn chaum_pedersen_transcript(
+    h: &RistrettoPoint,
+    x: &RistrettoPoint,
+    y: &RistrettoPoint,
+    a: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> Transcript {
+    let mut transcript = Transcript::new(CHAUM_PEDERSEN_LABEL);
+    for (label, point) in [(&b"h"[..], h), (b"x", x), (b"y", y), (b"a", a), (b"b", b)] {
+        transcript.append_message(label, point.compress().as_bytes());
+    }
+    transcript
+}
+
+impl ChaumPedersenProof {
+    // Proves X = secret·G and Y = secret·H.
+    pub fn prove<R: RngCore + CryptoRng>(secret: &Scalar, h: &RistrettoPoint, rng: &mut R) -> Self {
+        let x = RistrettoPoint::mul_base(secret);
+        let y = h * secret;
+        let k = Scalar::random(rng);
+        let a = RistrettoPoint::mul_base(&k);
+        let b = h * k;
+        let c = challenge_scalar(&mut chaum_pedersen_transcript(h, &x, &y, &a, &b));
+        ChaumPedersenProof {
+            c,
+            s: k + c * secret,
+        }
+    }
+
+    pub fn verify(&self, h: &RistrettoPoint, x: &RistrettoPoint, y: &RistrettoPoint) -> bool {
+        let a = RistrettoPoint::mul_base(&self.s) - x * self.c;
+        let b = h * self.s - y * self.c;
+        self.c == challenge_scalar(&mut chaum_pedersen_transcript(h, x, y, &a, &b))
+    }
+}
+
+// Evaluates the polynomial with public coefficient commitments at `index`.
+fn evaluate_commitments(commitments: &[RistrettoPoint], index: ShareIndex) -> RistrettoPoint {
+    commitments
+        .iter()
+        .rev()
+        .fold(RistrettoPoint::identity(), |acc, c| {
+            acc * Scalar::from(index) + c
+        })
+}
+
+// One player's contribution to a threshold key: a fresh random secret split with a random
+// polynomial of degree `threshold - 1` (Feldman secret sharing). The table's key is the sum
+// of every player's secret, so nobody knows it, and no player's identity key is involved.
+// Deal anew for every table. Since every share is checked against the commitments, a
+// dealer cannot commit to a secret they do not know.
+#[derive(Debug, Clone)]
+pub struct Dealing {
+    pub commitments: Vec<RistrettoPoint>,
+    shares: Vec<Scalar>,
+}
+
+impl Dealing {
+    pub fn new<R: RngCore + CryptoRng>(
+        threshold: usize,
+        players: usize,
+        rng: &mut R,
+    ) -> Result<Self, ThresholdError> {
+        if threshold == 0 || threshold > players {
+            return Err(ThresholdError::InvalidThreshold { threshold, players });
+        }
+        let coefficients: Vec<Scalar> = (0..threshold).map(|_| Scalar::random(rng)).collect();
+        let commitments = coefficients.iter().map(RistrettoPoint::mul_base).collect();
+        let shares = (1..=players as ShareIndex)
+            .map(|index| {
+                coefficients
+                    .iter()
+                    .rev()
+                    .fold(Scalar::ZERO, |acc, a| acc * Scalar::from(index) + a)
+            })
+            .collect();
+        Ok(Dealing {
+            commitments,
+            shares,
+        })
+    }
+
+    // The secret share for the player at `index`, from 1 to the number of players; it must
+    // be sent to them privately.
+    pub fn share_for(&self, index: ShareIndex) -> Option<Scalar> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.shares.get(i as usize))
+            .copied()
+    }
+}
+
+pub fn verify_dealt_share(
+    commitments: &[RistrettoPoint],
+    index: ShareIndex,
+    share: &Scalar,
+) -> bool {
+    RistrettoPoint::mul_base(share) == evaluate_commitments(commitments, index)
+}
+
+// Everything about the table's threshold key that anyone can know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdPublic {
+    pub threshold: usize,
+    pub joint_key: RistrettoPoint,
+    // s_j·G for each player's key share s_j, by share index starting at 1.
+    pub share_keys: Vec<RistrettoPoint>,
+}
+
+impl ThresholdPublic {
+    // Combines every player's published commitments, in seat order.
+    pub fn new(commitments: &[Vec<RistrettoPoint>]) -> Result<Self, ThresholdError> {
+        let threshold = commitments.first().map_or(0, Vec::len);
+        if threshold == 0 || threshold > commitments.len() {
+            return Err(ThresholdError::InvalidThreshold {
+                threshold,
+                players: commitments.len(),
+            });
+        }
+        if let Some(seat) = commitments
+            .iter()
+            .position(|dealt| dealt.len() != threshold)
+        {
+            return Err(ThresholdError::InvalidDealing(seat));
+        }
+
+        let share_keys = (1..=commitments.len() as ShareIndex)
+            .map(|index| {
+                commitments
+                    .iter()
+                    .map(|dealt| evaluate_commitments(dealt, index))
+                    .sum()
+            })
+            .collect();
+        Ok(ThresholdPublic {
+            threshold,
+            joint_key: commitments.iter().map(|dealt| dealt[0]).sum(),
+            share_keys,
+        })
+    }
+
+    fn share_key(&self, index: ShareIndex) -> Option<&RistrettoPoint> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.share_keys.get(i as usize))
+    }
+
+    pub fn verify_share(&self, ciphertext: &Ciphertext, share: &ThresholdShare) -> bool {
+        self.share_key(share.index)
+            .is_some_and(|key| share.proof.verify(&ciphertext.c1, key, &share.share))
+    }
+
+    // Opens a card from at least `threshold` verified shares.
+    pub fn open_card(
+        &self,
+        ciphertext: &Ciphertext,
+        shares: &[ThresholdShare],
+    ) -> Result<Card, ThresholdError> {
+        let mut used: Vec<&ThresholdShare> = Vec::new();
+        for share in shares {
+            if used.iter().any(|other| other.index == share.index) {
+                return Err(ThresholdError::DuplicateShare(share.index));
+            }
+            if !self.verify_share(ciphertext, share) {
+                return Err(ThresholdError::InvalidShare(share.index));
+            }
+            used.push(share);
+        }
+        if used.len() < self.threshold {
+            return Err(ThresholdError::NotEnoughShares {
+                have: used.len(),
+                need: self.threshold,
+            });
+        }
+        used.truncate(self.threshold);
+
+        let indices: Vec<ShareIndex> = used.iter().map(|share| share.index).collect();
+        let mask: RistrettoPoint = used
+            .iter()
+            .map(|share| share.share * lagrange_at_zero(share.index, &indices))
+            .sum();
+        decode_card(&(ciphertext.c2 - mask)).ok_or(ThresholdError::NotACard)
+    }
+}
+
+fn lagrange_at_zero(index: ShareIndex, indices: &[ShareIndex]) -> Scalar {
+    indices
+        .iter()
+        .filter(|&&other| other != index)
+        .fold(Scalar::ONE, |acc, &other| {
+            let other = Scalar::from(other);
+            acc * other * (other - Scalar::from(index)).invert()
+        })
+}
+
+// A player's share of the table's threshold key.
+#[derive(Debug, Clone)]
+pub struct ThresholdKey {
+    pub index: ShareIndex,
+    secret: Scalar,
+    pub public: ThresholdPublic,
+}
+
+impl ThresholdKey {
+    // Builds this player's key share from the shares every dealer sent them, in seat order.
+    pub fn new(
+        index: ShareIndex,
+        public: ThresholdPublic,
+        commitments: &[Vec<RistrettoPoint>],
+        received: &[Scalar],
+    ) -> Result<Self, ThresholdError> {
+        for (seat, (dealt, share)) in commitments.iter().zip(received).enumerate() {
+            if !verify_dealt_share(dealt, index, share) {
+                return Err(ThresholdError::InvalidDealing(seat));
+            }
+        }
+        if received.len() != commitments.len() {
+            return Err(ThresholdError::InvalidDealing(
+                received.len().min(commitments.len()),
+            ));
+        }
+        Ok(ThresholdKey {
+            index,
+            secret: received.iter().sum(),
+            public,
+        })
+    }
+
+    pub fn decryption_share<R: RngCore + CryptoRng>(
+        &self,
+        ciphertext: &Ciphertext,
+        rng: &mut R,
+    ) -> ThresholdShare {
+        ThresholdShare {
+            index: self.index,
+            share: ciphertext.c1 * self.secret,
+            proof: ChaumPedersenProof::prove(&self.secret, &ciphertext.c1, rng),
+        }
+    }
+}
+
+// A partial decryption s_j·c1 with a Chaum-Pedersen proof that it used the key share
+// behind the public share key s_j·G.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdShare {
+    pub index: ShareIndex,
+    pub share: RistrettoPoint,
+    pub proof: ChaumPedersenProof,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mental::{board_position, EncryptedDeck};
+    use crate::transcript::test_deck_context;
+    use rand::rngs::OsRng;
+
+    struct Table {
+        keys: Vec<ThresholdKey>,
+    }
+
+    fn table(threshold: usize, n: usize) -> Table {
+        let dealings: Vec<Dealing> = (0..n)
+            .map(|_| Dealing::new(threshold, n, &mut OsRng).unwrap())
+            .collect();
+        let commitments: Vec<Vec<RistrettoPoint>> = dealings
+            .iter()
+            .map(|dealing| dealing.commitments.clone())
+            .collect();
+        let public = ThresholdPublic::new(&commitments).unwrap();
+        let keys = (1..=n as ShareIndex)
+            .map(|index| {
+                let received: Vec<Scalar> = dealings
+                    .iter()
+                    .map(|dealing| dealing.share_for(index).unwrap())
+                    .collect();
+                ThresholdKey::new(index, public.clone(), &commitments, &received).unwrap()
+            })
+            .collect();
+        Table { keys }
+    }
+
+    fn shares(table: &Table, ciphertext: &Ciphertext, indices: &[usize]) -> Vec<ThresholdShare> {
+        indices
+            .iter()
+            .map(|&i| table.keys[i].decryption_share(ciphertext, &mut OsRng))
+            .collect()
+    }
+
+    #[test]
+    fn test_chaum_pedersen() {
+        let secret = Scalar::random(&mut OsRng);
+        let h = RistrettoPoint::mul_base(&Scalar::random(&mut OsRng));
+        let proof = ChaumPedersenProof::prove(&secret, &h, &mut OsRng);
+        let x = RistrettoPoint::mul_base(&secret);
+        assert!(proof.verify(&h, &x, &(h * secret)));
+        assert!(!proof.verify(&h, &x, &(h * (secret + Scalar::ONE))));
+        assert!(!proof.verify(&x, &h, &(h * secret)));
+    }
+
+    #[test]
+    fn test_dealing_parameters_are_checked() {
+        assert_eq!(
+            Dealing::new(0, 3, &mut OsRng).err(),
+            Some(ThresholdError::InvalidThreshold {
+                threshold: 0,
+                players: 3
+            })
+        );
+        assert_eq!(
+            Dealing::new(4, 3, &mut OsRng).err(),
+            Some(ThresholdError::InvalidThreshold {
+                threshold: 4,
+                players: 3
+            })
+        );
+        let dealing = Dealing::new(2, 3, &mut OsRng).unwrap();
+        assert_eq!(dealing.share_for(0), None);
+        assert!(dealing.share_for(3).is_some());
+        assert_eq!(dealing.share_for(4), None);
+    }
+
+    #[test]
+    fn test_every_dealing_is_fresh() {
+        let first = table(2, 3);
+        let second = table(2, 3);
+        assert_ne!(
+            first.keys[0].public.joint_key,
+            second.keys[0].public.joint_key
+        );
+    }
+
+    #[test]
+    fn test_open_board_with_any_quorum() {
+        let table = table(3, 5);
+        let deck = EncryptedDeck::new(test_deck_context(), table.keys[0].public.joint_key)
+            .shuffle(&mut OsRng);
+        let public = &table.keys[0].public;
+
+        let flop: Vec<&Ciphertext> = (0..3)
+            .map(|n| &deck.cards()[board_position(n, 5)])
+            .collect();
+        for ciphertext in flop {
+            let first = public
+                .open_card(ciphertext, &shares(&table, ciphertext, &[0, 1, 2]))
+                .unwrap();
+            let second = public
+                .open_card(ciphertext, &shares(&table, ciphertext, &[4, 2, 3]))
+                .unwrap();
+            let everyone = public
+                .open_card(ciphertext, &shares(&table, ciphertext, &[0, 1, 2, 3, 4]))
+                .unwrap();
+            assert_eq!(first, second);
+            assert_eq!(first, everyone);
+        }
+    }
+
+    #[test]
+    fn test_all_players_required_when_threshold_is_n() {
+        let table = table(3, 3);
+        let deck = EncryptedDeck::new(test_deck_context(), table.keys[0].public.joint_key)
+            .shuffle(&mut OsRng);
+        let ciphertext = &deck.cards()[0];
+        let public = &table.keys[0].public;
+        assert_eq!(
+            public.open_card(ciphertext, &shares(&table, ciphertext, &[0, 2])),
+            Err(ThresholdError::NotEnoughShares { have: 2, need: 3 })
+        );
+        assert!(public
+            .open_card(ciphertext, &shares(&table, ciphertext, &[0, 1, 2]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_bad_shares_are_rejected() {
+        let table = table(2, 3);
+        let deck = EncryptedDeck::new(test_deck_context(), table.keys[0].public.joint_key)
+            .shuffle(&mut OsRng);
+        let ciphertext = &deck.cards()[0];
+        let public = &table.keys[0].public;
+
+        let mut forged = shares(&table, ciphertext, &[0, 1]);
+        forged[1].share += RistrettoPoint::mul_base(&Scalar::ONE);
+        assert_eq!(
+            public.open_card(ciphertext, &forged),
+            Err(ThresholdError::InvalidShare(2))
+        );
+
+        // A share for a different card does not verify either.
+        let other = shares(&table, &deck.cards()[1], &[2]);
+        assert!(!public.verify_share(ciphertext, &other[0]));
+
+        let duplicated = shares(&table, ciphertext, &[0, 0]);
+        assert_eq!(
+            public.open_card(ciphertext, &duplicated),
+            Err(ThresholdError::DuplicateShare(1))
+        );
+
+        let mut unknown = shares(&table, ciphertext, &[0])[0];
+        unknown.index = 9;
+        assert!(!public.verify_share(ciphertext, &unknown));
+    }
+
+    #[test]
+    fn test_bad_dealing_is_detected() {
+        let dealings: Vec<Dealing> = (0..3)
+            .map(|_| Dealing::new(2, 3, &mut OsRng).unwrap())
+            .collect();
+        let mut commitments: Vec<Vec<RistrettoPoint>> = dealings
+            .iter()
+            .map(|dealing| dealing.commitments.clone())
+            .collect();
+        let public = ThresholdPublic::new(&commitments).unwrap();
+
+        // Seat 1 sends seat 0 a share that does not match its commitments.
+        let mut received: Vec<Scalar> = dealings
+            .iter()
+            .map(|dealing| dealing.share_for(1).unwrap())
+            .collect();
+        received[1] += Scalar::ONE;
+        assert_eq!(
+            ThresholdKey::new(1, public, &commitments, &received).err(),
+            Some(ThresholdError::InvalidDealing(1))
+        );
+
+        // Seat 2 deals a polynomial of a different degree.
+        commitments[2].pop();
+        assert_eq!(
+            ThresholdPublic::new(&commitments),
+            Err(ThresholdError::InvalidDealing(2))
+        );
+    }
+}
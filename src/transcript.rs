@@ -0,0 +1,190 @@
+use crate::wire::hex_encoded;
+use merlin::Transcript;
+use rand::{rngs::OsRng, RngCore};
+use schnorrkel::PublicKey;
+use serde::{Deserialize, Serialize};
+
+pub const DRAW_LABEL: &[u8] = b"vrf-poker-draw";
+
+pub type GameId = [u8; 32];
+pub type TableId = u32;
+
+pub fn random_game_id() -> GameId {
+    let mut id = [0u8; 32];
+    OsRng.fill_bytes(&mut id);
+    id
+}
+
+// Which card a VRF draw is for within a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardSlot {
+    // One of the drawing player's own cards, by dealing order.
+    Hole(u8),
+    // The player's contribution to the community cards.
+    Board,
+}
+
+// Everything a VRF draw is bound to. Each field goes into the signed transcript, so a
+// proof made for one game, table, round or card slot does not verify for any other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DrawContext {
+    #[serde(with = "hex_encoded")]
+    pub game_id: GameId,
+    pub table_id: TableId,
+    pub round: u32,
+    pub slot: CardSlot,
+    // The round's shared randomness, e.g. from commit-reveal.
+    #[serde(with = "hex_encoded")]
+    pub input: Vec<u8>,
+}
+
+impl DrawContext {
+    pub fn new(
+        game_id: GameId,
+        table_id: TableId,
+        round: u32,
+        slot: CardSlot,
+        input: &[u8],
+    ) -> Self {
+        DrawContext {
+            game_id,
+            table_id,
+            round,
+            slot,
+            input: input.to_vec(),
+        }
+    }
+
+    pub fn with_slot(&self, slot: CardSlot) -> Self {
+        DrawContext {
+            slot,
+            ..self.clone()
+        }
+    }
+
+    // The VRF input `public` signs for this draw. The key is bound too, so one player's
+    // proof can never be passed off as another's.
+    pub fn transcript(&self, public: &PublicKey) -> Transcript {
+        let mut transcript = Transcript::new(DRAW_LABEL);
+        transcript.append_message(b"game", &self.game_id);
+        transcript.append_u64(b"table", self.table_id.into());
+        transcript.append_u64(b"round", self.round.into());
+        match self.slot {
+            CardSlot::Hole(index) => {
+                transcript.append_message(b"slot", b"hole");
+                transcript.append_u64(b"index", index.into());
+            }
+            CardSlot::Board => transcript.append_message(b"slot", b"board"),
+        }
+        transcript.append_message(b"public", public.as_ref());
+        transcript.append_message(b"input", &self.input);
+        transcript
+    }
+}
+
+// The hand a deck is dealt for. It goes into every shuffle proof and decryption share made
+// on the deck, so neither can be replayed in another game, table or hand.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeckContext {
+    pub game_id: GameId,
+    pub table_id: TableId,
+    pub hand: u32,
+    // The hand's round input, agreed with commit-reveal.
+    pub input: Vec<u8>,
+}
+
+impl DeckContext {
+    pub fn new(game_id: GameId, table_id: TableId, hand: u32, input: &[u8]) -> Self {
+        DeckContext {
+            game_id,
+            table_id,
+            hand,
+            input: input.to_vec(),
+        }
+    }
+
+    pub fn append_to(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"game", &self.game_id);
+        transcript.append_u64(b"table", self.table_id.into());
+        transcript.append_u64(b"hand", self.hand.into());
+        transcript.append_message(b"input", &self.input);
+    }
+}
+
+// A draw context for tests that only care about the input bytes.
+#[cfg(test)]
+pub(crate) fn test_context(input: &[u8]) -> DrawContext {
+    DrawContext::new([0; 32], 0, 0, CardSlot::Hole(0), input)
+}
+
+// A deck context for tests that deal a single hand.
+#[cfg(test)]
+pub(crate) fn test_deck_context() -> DeckContext {
+    DeckContext::new([0; 32], 0, 1, b"input")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    #[test]
+    fn test_every_field_changes_the_transcript() {
+        let public = Player::new().public_key();
+        let base = DrawContext::new([1; 32], 2, 3, CardSlot::Hole(0), b"input");
+        let challenge = |context: &DrawContext, public: &PublicKey| {
+            let mut bytes = [0u8; 32];
+            context
+                .transcript(public)
+                .challenge_bytes(b"test", &mut bytes);
+            bytes
+        };
+
+        let variants = [
+            DrawContext {
+                game_id: [9; 32],
+                ..base.clone()
+            },
+            DrawContext {
+                table_id: 9,
+                ..base.clone()
+            },
+            DrawContext {
+                round: 9,
+                ..base.clone()
+            },
+            base.with_slot(CardSlot::Hole(1)),
+            base.with_slot(CardSlot::Board),
+            DrawContext::new([1; 32], 2, 3, CardSlot::Hole(0), b"other"),
+        ];
+        let expected = challenge(&base, &public);
+        assert_eq!(challenge(&base.clone(), &public), expected);
+        for context in &variants {
+            assert_ne!(challenge(context, &public), expected);
+        }
+        assert_ne!(challenge(&base, &Player::new().public_key()), expected);
+    }
+
+    #[test]
+    fn test_every_deck_field_changes_the_transcript() {
+        let base = DeckContext::new([1; 32], 2, 3, b"input");
+        let challenge = |context: &DeckContext| {
+            let mut transcript = Transcript::new(b"test");
+            context.append_to(&mut transcript);
+            let mut bytes = [0u8; 32];
+            transcript.challenge_bytes(b"test", &mut bytes);
+            bytes
+        };
+
+        let variants = [
+            DeckContext::new([9; 32], 2, 3, b"input"),
+            DeckContext::new([1; 32], 9, 3, b"input"),
+            DeckContext::new([1; 32], 2, 9, b"input"),
+            DeckContext::new([1; 32], 2, 3, b"other"),
+        ];
+        for context in &variants {
+            assert_ne!(challenge(context), challenge(&base));
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::card::Card;
+use crate::deck::Deck;
+use crate::error::PokerError;
+use crate::transcript::DrawContext;
+use schnorrkel::{
+    vrf::{vrf_verify_batch, VRFInOut, VRFPreOut, VRFProofBatchable},
+    PublicKey,
+};
+use std::fmt;
+
+// Positions of the claims whose proofs failed, in the order they were passed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidClaims(pub Vec<usize>);
+
+impl fmt::Display for InvalidClaims {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid VRF proofs at positions {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidClaims {}
+
+// Checks a VRF draw using only public data and returns the verified output.
+pub fn verify_draw(
+    public: &PublicKey,
+    context: &DrawContext,
+    output: &VRFPreOut,
+    proof: &VRFProofBatchable,
+) -> Result<VRFInOut, PokerError> {
+    let proof = proof
+        .shorten_vrf(public, context.transcript(public), output)
+        .map_err(|_| PokerError::InvalidProof)?;
+    public
+        .vrf_verify(context.transcript(public), output, &proof)
+        .map(|(inout, _)| inout)
+        .map_err(|_| PokerError::InvalidProof)
+}
+
+// Checks many draws, possibly from many players, in one batched verification and returns
+// their outputs in order. If the batch fails, every claim is checked on its own so the
+// error names exactly which ones are bad.
+pub fn verify_claims<'a>(
+    claims: impl IntoIterator<Item = &'a CardClaim>,
+) -> Result<Vec<VRFInOut>, InvalidClaims> {
+    let claims: Vec<&CardClaim> = claims.into_iter().collect();
+    if claims.is_empty() {
+        return Ok(Vec::new());
+    }
+    let transcripts = claims
+        .iter()
+        .map(|claim| claim.context.transcript(&claim.public));
+    let outputs: Vec<VRFPreOut> = claims.iter().map(|claim| claim.output).collect();
+    let proofs: Vec<VRFProofBatchable> = claims.iter().map(|claim| claim.proof.clone()).collect();
+    let publics: Vec<PublicKey> = claims.iter().map(|claim| claim.public).collect();
+    if let Ok(inouts) = vrf_verify_batch(transcripts, &outputs, &proofs, &publics) {
+        return Ok(inouts.into_vec());
+    }
+
+    let results: Vec<Result<VRFInOut, PokerError>> =
+        claims.iter().map(|claim| claim.verify()).collect();
+    let failed: Vec<usize> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| result.is_err())
+        .map(|(i, _)| i)
+        .collect();
+    if failed.is_empty() {
+        Ok(results.into_iter().flatten().collect())
+    } else {
+        Err(InvalidClaims(failed))
+    }
+}
+
+// Batch-checks `claims` and reports whether each one verified, in order.
+pub fn verify_each<'a>(claims: impl IntoIterator<Item = &'a CardClaim>) -> Vec<bool> {
+    let claims: Vec<&CardClaim> = claims.into_iter().collect();
+    match verify_claims(claims.iter().copied()) {
+        Ok(_) => vec![true; claims.len()],
+        Err(InvalidClaims(failed)) => (0..claims.len()).map(|i| !failed.contains(&i)).collect(),
+    }
+}
+
+// Everything a player publishes about one VRF draw, so opponents, spectators and auditors
+// can check it without the player's secret key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardClaim {
+    pub public: PublicKey,
+    pub context: DrawContext,
+    pub output: VRFPreOut,
+    // The batchable form of the proof, so a whole round can be checked at once.
+    pub proof: VRFProofBatchable,
+}
+
+impl CardClaim {
+    pub fn verify(&self) -> Result<VRFInOut, PokerError> {
+        verify_draw(&self.public, &self.context, &self.output, &self.proof)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.verify().is_ok()
+    }
+
+    // The card this claim selects from `deck`, removing it. The deck is left alone if the
+    // claim does not verify.
+    pub fn card_from(&self, deck: &mut Deck) -> Result<Card, PokerError> {
+        let inout = self.verify()?;
+        deck.draw(&inout).ok_or(PokerError::DeckEmpty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::transcript::test_context;
+
+    #[test]
+    fn test_verify_draw_with_public_data() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"input"));
+        let output = player.vrf_output().unwrap().to_preout();
+        let proof = player.vrf_proof().unwrap();
+        let public = player.public_key();
+        assert!(verify_draw(&public, &test_context(b"input"), &output, proof).is_ok());
+        assert!(verify_draw(&public, &test_context(b"wrong"), &output, proof).is_err());
+        assert!(verify_draw(
+            &Player::new().public_key(),
+            &test_context(b"input"),
+            &output,
+            proof
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_claim_card_matches_player() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"input"));
+        let claim = player.claim(&test_context(b"input")).unwrap();
+        assert!(claim.is_valid());
+        assert_eq!(claim.card_from(&mut Deck::new()), player.reveal_card());
+    }
+
+    #[test]
+    fn test_tampered_claims_are_rejected() {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"input"));
+        player.draw_card(&test_context(b"other"));
+        let other = player.claim(&test_context(b"other")).unwrap();
+
+        assert_eq!(
+            player.claim(&test_context(b"input")),
+            Err(PokerError::InputMismatch)
+        );
+
+        // Relabelling a claim with another context breaks its proof.
+        let mut claim = other.clone();
+        claim.context = test_context(b"input");
+        assert_eq!(claim.verify(), Err(PokerError::InvalidProof));
+        claim.context = test_context(b"other");
+        assert!(claim.is_valid());
+
+        claim.public = Player::new().public_key();
+        assert!(!claim.is_valid());
+        let mut deck = Deck::new();
+        assert_eq!(claim.card_from(&mut deck), Err(PokerError::InvalidProof));
+        assert_eq!(deck.len(), 52);
+
+        let mut claim = other.clone();
+        let mut bytes = claim.output.to_bytes();
+        bytes[0] ^= 1;
+        claim.output = VRFPreOut(bytes);
+        assert!(!claim.is_valid());
+    }
+
+    fn claims(players: usize) -> Vec<CardClaim> {
+        (0..players)
+            .map(|i| {
+                let mut player = Player::new();
+                let context = test_context(&[i as u8]);
+                player.draw_card(&context);
+                player.claim(&context).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_matches_single_verification() {
+        let claims = claims(8);
+        let outputs = verify_claims(&claims).unwrap();
+        assert_eq!(outputs.len(), 8);
+        for (claim, output) in claims.iter().zip(&outputs) {
+            assert_eq!(&claim.verify().unwrap(), output);
+        }
+        assert_eq!(verify_claims(&[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_batch_pinpoints_bad_proofs() {
+        let mut claims = claims(6);
+        claims[1].context = test_context(b"replayed");
+        claims[4].proof = claims[3].proof.clone();
+        assert_eq!(verify_claims(&claims), Err(InvalidClaims(vec![1, 4])));
+        assert_eq!(
+            verify_each(&claims),
+            vec![true, false, true, true, false, true]
+        );
+    }
+}
@@ -0,0 +1,796 @@
+use crate::betting::Action;
+use crate::commit::{Commitment, Contribution};
+use crate::mental::{Ciphertext, DecryptionShare, KeyAnnouncement};
+use crate::shuffle::ShuffleProof;
+use crate::transcript::{DrawContext, GameId};
+use crate::verify::CardClaim;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use schnorrkel::{
+    signing_context,
+    vrf::{VRFPreOut, VRFProof, VRFProofBatchable},
+    Keypair, PublicKey, Signature,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// Bumped whenever the encoding of any message changes incompatibly.
+pub const WIRE_VERSION: u16 = 5;
+
+pub const MESSAGE_CONTEXT: &[u8] = b"vrf-poker-message";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    InvalidHex,
+    InvalidBytes(&'static str),
+    InvalidEncoding(String),
+    UnsupportedVersion(u16),
+    BadSignature,
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::InvalidHex => write!(f, "invalid hex string"),
+            WireError::InvalidBytes(what) => write!(f, "invalid bytes for {}", what),
+            WireError::InvalidEncoding(reason) => write!(f, "invalid encoding: {}", reason),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "unsupported wire version {}", version)
+            }
+            WireError::BadSignature => write!(f, "message signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+// Types with a fixed canonical byte encoding.
+pub trait WireBytes: Sized {
+    fn to_wire(&self) -> Vec<u8>;
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError>;
+
+    fn to_hex(&self) -> String {
+        hex::encode(self.to_wire())
+    }
+
+    fn from_hex(s: &str) -> Result<Self, WireError> {
+        let bytes = hex::decode(s).map_err(|_| WireError::InvalidHex)?;
+        Self::from_wire(&bytes)
+    }
+}
+
+impl WireBytes for PublicKey {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        PublicKey::from_bytes(bytes).map_err(|_| WireError::InvalidBytes("public key"))
+    }
+}
+
+impl WireBytes for VRFPreOut {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        VRFPreOut::from_bytes(bytes).map_err(|_| WireError::InvalidBytes("VRF output"))
+    }
+}
+
+impl WireBytes for VRFProof {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        VRFProof::from_bytes(bytes).map_err(|_| WireError::InvalidBytes("VRF proof"))
+    }
+}
+
+impl WireBytes for VRFProofBatchable {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        VRFProofBatchable::from_bytes(bytes)
+            .map_err(|_| WireError::InvalidBytes("batchable VRF proof"))
+    }
+}
+
+impl WireBytes for Signature {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        Signature::from_bytes(bytes).map_err(|_| WireError::InvalidBytes("signature"))
+    }
+}
+
+impl WireBytes for Vec<u8> {
+    fn to_wire(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl WireBytes for [u8; 32] {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        bytes
+            .try_into()
+            .map_err(|_| WireError::InvalidBytes("32-byte value"))
+    }
+}
+
+impl WireBytes for RistrettoPoint {
+    fn to_wire(&self) -> Vec<u8> {
+        self.compress().to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        CompressedRistretto::from_slice(bytes)
+            .ok()
+            .and_then(|point| point.decompress())
+            .ok_or(WireError::InvalidBytes("Ristretto point"))
+    }
+}
+
+impl WireBytes for Scalar {
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| WireError::InvalidBytes("scalar"))?;
+        Option::from(Scalar::from_canonical_bytes(bytes)).ok_or(WireError::InvalidBytes("scalar"))
+    }
+}
+
+impl WireBytes for Ciphertext {
+    fn to_wire(&self) -> Vec<u8> {
+        [self.c1.to_wire(), self.c2.to_wire()].concat()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        if bytes.len() != 64 {
+            return Err(WireError::InvalidBytes("ciphertext"));
+        }
+        Ok(Ciphertext {
+            c1: RistrettoPoint::from_wire(&bytes[..32])?,
+            c2: RistrettoPoint::from_wire(&bytes[32..])?,
+        })
+    }
+}
+
+// Fixed-size items back to back, e.g. a whole deck of ciphertexts.
+fn concat_wire<T: WireBytes>(items: &[T]) -> Vec<u8> {
+    items.iter().flat_map(WireBytes::to_wire).collect()
+}
+
+fn split_wire<T: WireBytes>(
+    bytes: &[u8],
+    size: usize,
+    what: &'static str,
+) -> Result<Vec<T>, WireError> {
+    if !bytes.len().is_multiple_of(size) {
+        return Err(WireError::InvalidBytes(what));
+    }
+    bytes.chunks(size).map(T::from_wire).collect()
+}
+
+impl WireBytes for Vec<Ciphertext> {
+    fn to_wire(&self) -> Vec<u8> {
+        concat_wire(self)
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        split_wire(bytes, 64, "ciphertexts")
+    }
+}
+
+impl WireBytes for Vec<RistrettoPoint> {
+    fn to_wire(&self) -> Vec<u8> {
+        concat_wire(self)
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        split_wire(bytes, 32, "points")
+    }
+}
+
+impl WireBytes for Vec<Scalar> {
+    fn to_wire(&self) -> Vec<u8> {
+        concat_wire(self)
+    }
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, WireError> {
+        split_wire(bytes, 32, "scalars")
+    }
+}
+
+// Serde adapter for `WireBytes` fields: a hex string in human-readable formats such as
+// JSON, and raw bytes in binary ones.
+pub mod hex_encoded {
+    use super::WireBytes;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: WireBytes, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&value.to_hex())
+        } else {
+            serializer.serialize_bytes(&value.to_wire())
+        }
+    }
+
+    pub fn deserialize<'de, T: WireBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            T::from_hex(&s).map_err(Error::custom)
+        } else {
+            let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+            T::from_wire(&bytes).map_err(Error::custom)
+        }
+    }
+}
+
+// A `PublicKey` that can be used directly as a serde value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WirePublicKey(pub PublicKey);
+
+impl Serialize for WirePublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        hex_encoded::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WirePublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        hex_encoded::deserialize(deserializer).map(WirePublicKey)
+    }
+}
+
+impl Serialize for CardClaim {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WireClaim::from(self.clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CardClaim {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        WireClaim::deserialize(deserializer).map(CardClaim::from)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireClaim {
+    #[serde(with = "hex_encoded")]
+    public: PublicKey,
+    context: DrawContext,
+    #[serde(with = "hex_encoded")]
+    output: VRFPreOut,
+    #[serde(with = "hex_encoded")]
+    proof: VRFProofBatchable,
+}
+
+impl From<CardClaim> for WireClaim {
+    fn from(claim: CardClaim) -> Self {
+        WireClaim {
+            public: claim.public,
+            context: claim.context,
+            output: claim.output,
+            proof: claim.proof,
+        }
+    }
+}
+
+impl From<WireClaim> for CardClaim {
+    fn from(claim: WireClaim) -> Self {
+        CardClaim {
+            public: claim.public,
+            context: claim.context,
+            output: claim.output,
+            proof: claim.proof,
+        }
+    }
+}
+
+impl Serialize for KeyAnnouncement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WireAnnouncement {
+            key: self.key,
+            proof: self.proof,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyAnnouncement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let announcement = WireAnnouncement::deserialize(deserializer)?;
+        Ok(KeyAnnouncement {
+            key: announcement.key,
+            proof: announcement.proof,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireAnnouncement {
+    #[serde(with = "hex_encoded")]
+    key: PublicKey,
+    #[serde(with = "hex_encoded")]
+    proof: Signature,
+}
+
+impl Serialize for DecryptionShare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WireShare {
+            public: self.public,
+            share: self.share,
+            proof: self.proof.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DecryptionShare {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let share = WireShare::deserialize(deserializer)?;
+        Ok(DecryptionShare {
+            public: share.public,
+            share: share.share,
+            proof: share.proof,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireShare {
+    #[serde(with = "hex_encoded")]
+    public: PublicKey,
+    #[serde(with = "hex_encoded")]
+    share: RistrettoPoint,
+    #[serde(with = "hex_encoded")]
+    proof: VRFProof,
+}
+
+impl Serialize for ShuffleProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WireShuffleProof {
+            c: self.c.clone(),
+            c_hat: self.c_hat.clone(),
+            t: vec![self.t1, self.t2, self.t3],
+            t4: self.t4,
+            t_hat: self.t_hat.clone(),
+            s: vec![self.s1, self.s2, self.s3, self.s4],
+            s_hat: self.s_hat.clone(),
+            s_prime: self.s_prime.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ShuffleProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let proof = WireShuffleProof::deserialize(deserializer)?;
+        let (&[t1, t2, t3], &[s1, s2, s3, s4]) = (proof.t.as_slice(), proof.s.as_slice()) else {
+            return Err(serde::de::Error::custom(
+                "shuffle proof has the wrong number of values",
+            ));
+        };
+        Ok(ShuffleProof {
+            c: proof.c,
+            c_hat: proof.c_hat,
+            t1,
+            t2,
+            t3,
+            t4: proof.t4,
+            t_hat: proof.t_hat,
+            s1,
+            s2,
+            s3,
+            s4,
+            s_hat: proof.s_hat,
+            s_prime: proof.s_prime,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireShuffleProof {
+    #[serde(with = "hex_encoded")]
+    c: Vec<RistrettoPoint>,
+    #[serde(with = "hex_encoded")]
+    c_hat: Vec<RistrettoPoint>,
+    #[serde(with = "hex_encoded")]
+    t: Vec<RistrettoPoint>,
+    #[serde(with = "hex_encoded")]
+    t4: Ciphertext,
+    #[serde(with = "hex_encoded")]
+    t_hat: Vec<RistrettoPoint>,
+    #[serde(with = "hex_encoded")]
+    s: Vec<Scalar>,
+    #[serde(with = "hex_encoded")]
+    s_hat: Vec<Scalar>,
+    #[serde(with = "hex_encoded")]
+    s_prime: Vec<Scalar>,
+}
+
+// Everything a player can say to the rest of the table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Message {
+    Commit {
+        commitment: Commitment,
+    },
+    Reveal {
+        #[serde(with = "hex_encoded")]
+        contribution: Contribution,
+    },
+    // A shuffle pass over the current deck.
+    Shuffle {
+        #[serde(with = "hex_encoded")]
+        deck: Vec<Ciphertext>,
+        proof: Box<ShuffleProof>,
+    },
+    // Decryption shares for every deck position the sender owes, in order.
+    Shares {
+        shares: Vec<DecryptionShare>,
+    },
+    Action {
+        action: Action,
+    },
+    // Sent first, with sequence number 0, to take a seat under this key. `deck_key` is
+    // the sender's deck key for this table.
+    Join {
+        name: String,
+        deck_key: Box<KeyAnnouncement>,
+    },
+}
+
+// A message signed by the player who sent it. The signature covers the version, the game
+// it was sent in, the sender's sequence number and the binary encoding of the message, so
+// it cannot be replayed into another game or at another point in this one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub version: u16,
+    #[serde(with = "hex_encoded")]
+    pub game_id: GameId,
+    // Counts up from zero for each sender in each game.
+    pub sequence: u64,
+    #[serde(with = "hex_encoded")]
+    pub public: PublicKey,
+    pub message: Message,
+    #[serde(with = "hex_encoded")]
+    pub signature: Signature,
+}
+
+fn signed_bytes(version: u16, game_id: &GameId, sequence: u64, message: &Message) -> Vec<u8> {
+    bincode::serialize(&(version, game_id, sequence, message)).expect("messages always encode")
+}
+
+impl SignedMessage {
+    pub fn sign(keypair: &Keypair, game_id: GameId, sequence: u64, message: Message) -> Self {
+        let bytes = signed_bytes(WIRE_VERSION, &game_id, sequence, &message);
+        let signature = keypair.sign(signing_context(MESSAGE_CONTEXT).bytes(&bytes));
+        SignedMessage {
+            version: WIRE_VERSION,
+            game_id,
+            sequence,
+            public: keypair.public,
+            message,
+            signature,
+        }
+    }
+
+    pub fn verify(&self) -> Result<(), WireError> {
+        if self.version != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(self.version));
+        }
+        let bytes = signed_bytes(self.version, &self.game_id, self.sequence, &self.message);
+        self.public
+            .verify(
+                signing_context(MESSAGE_CONTEXT).bytes(&bytes),
+                &self.signature,
+            )
+            .map_err(|_| WireError::BadSignature)
+    }
+
+    pub fn to_json(&self) -> String {
+        to_json(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, WireError> {
+        let message: SignedMessage = from_json(json)?;
+        message.check_version()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let message: SignedMessage = from_bytes(bytes)?;
+        message.check_version()
+    }
+
+    fn check_version(self) -> Result<Self, WireError> {
+        if self.version == WIRE_VERSION {
+            Ok(self)
+        } else {
+            Err(WireError::UnsupportedVersion(self.version))
+        }
+    }
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("wire types always encode")
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, WireError> {
+    serde_json::from_str(json).map_err(|e| WireError::InvalidEncoding(e.to_string()))
+}
+
+pub fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::serialize(value).expect("wire types always encode")
+}
+
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, WireError> {
+    bincode::deserialize(bytes).map_err(|e| WireError::InvalidEncoding(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+    use crate::commit::commit;
+    use crate::mental::{DeckKey, EncryptedDeck};
+    use crate::player::Player;
+    use crate::shuffle::prove_shuffle;
+    use crate::transcript::{test_context, test_deck_context};
+    use rand::rngs::OsRng;
+    use schnorrkel::{ExpansionMode, MiniSecretKey};
+
+    const GAME: GameId = [3; 32];
+
+    fn fixed_keypair() -> Keypair {
+        MiniSecretKey::from_bytes(&[1; 32])
+            .unwrap()
+            .expand_to_keypair(ExpansionMode::Uniform)
+    }
+
+    fn claim() -> CardClaim {
+        let mut player = Player::new();
+        player.draw_card(&test_context(b"input"));
+        player.claim(&test_context(b"input")).unwrap()
+    }
+
+    #[test]
+    fn test_primitive_round_trips() {
+        let claim = claim();
+        assert_eq!(
+            PublicKey::from_hex(&claim.public.to_hex()),
+            Ok(claim.public)
+        );
+        assert_eq!(
+            VRFPreOut::from_hex(&claim.output.to_hex()),
+            Ok(claim.output)
+        );
+        assert_eq!(
+            VRFProofBatchable::from_hex(&claim.proof.to_hex()),
+            Ok(claim.proof.clone())
+        );
+        assert_eq!(
+            PublicKey::from_wire(&claim.public.to_wire()),
+            Ok(claim.public)
+        );
+
+        let signature = fixed_keypair().sign(signing_context(b"test").bytes(b"message"));
+        assert_eq!(Signature::from_hex(&signature.to_hex()), Ok(signature));
+    }
+
+    #[test]
+    fn test_invalid_primitives() {
+        assert_eq!(PublicKey::from_hex("zz"), Err(WireError::InvalidHex));
+        assert_eq!(
+            PublicKey::from_hex("00"),
+            Err(WireError::InvalidBytes("public key"))
+        );
+        assert!(VRFProof::from_wire(&[0; 10]).is_err());
+        assert!(VRFProofBatchable::from_wire(&[0; 64]).is_err());
+        assert!(<[u8; 32]>::from_wire(&[0; 31]).is_err());
+    }
+
+    #[test]
+    fn test_public_key_encoding_is_stable() {
+        let public = fixed_keypair().public;
+        assert_eq!(public.to_hex().len(), 64);
+        assert_eq!(
+            to_json(&WirePublicKey(public)),
+            format!("\"{}\"", public.to_hex())
+        );
+        assert_eq!(to_bytes(&WirePublicKey(public))[8..], public.to_bytes());
+    }
+
+    #[test]
+    fn test_claim_round_trips() {
+        let claim = claim();
+        let json = to_json(&claim);
+        assert!(json.contains(&format!("\"input\":\"{}\"", hex::encode(b"input"))));
+        assert!(json.contains("\"slot\":{\"hole\":0}"));
+        let decoded: CardClaim = from_json(&json).unwrap();
+        assert_eq!(decoded, claim);
+        assert!(decoded.is_valid());
+
+        let decoded: CardClaim = from_bytes(&to_bytes(&claim)).unwrap();
+        assert_eq!(decoded, claim);
+    }
+
+    #[test]
+    fn test_mental_primitives_round_trip() {
+        let point = RistrettoPoint::mul_base(&Scalar::from(7u64));
+        assert_eq!(RistrettoPoint::from_hex(&point.to_hex()), Ok(point));
+        assert_eq!(Scalar::from_wire(&Scalar::ONE.to_wire()), Ok(Scalar::ONE));
+        let cards = EncryptedDeck::new(test_deck_context(), point)
+            .cards()
+            .to_vec();
+        assert_eq!(<Vec<Ciphertext>>::from_wire(&cards.to_wire()), Ok(cards));
+
+        assert!(RistrettoPoint::from_wire(&[0xff; 32]).is_err());
+        assert!(Scalar::from_wire(&[0xff; 32]).is_err());
+        assert!(<Vec<Ciphertext>>::from_wire(&[0; 63]).is_err());
+    }
+
+    #[test]
+    fn test_signed_message_round_trips() {
+        let keypair = fixed_keypair();
+        let mut key = DeckKey::generate(OsRng);
+        let deck = EncryptedDeck::new(test_deck_context(), *key.public().as_point());
+        let (shuffled, proof) = prove_shuffle(&deck, &mut OsRng);
+        key.use_verified_deck(shuffled.clone());
+        let messages = [
+            Message::Commit {
+                commitment: commit(&keypair.public, &[7; 32]),
+            },
+            Message::Reveal {
+                contribution: [7; 32],
+            },
+            Message::Shuffle {
+                deck: shuffled.cards().to_vec(),
+                proof: Box::new(proof),
+            },
+            Message::Shares {
+                shares: vec![key.decryption_share(0).unwrap()],
+            },
+            Message::Action {
+                action: Action::Raise(40),
+            },
+            Message::Join {
+                name: "Alice".to_string(),
+                deck_key: Box::new(key.announce(&keypair.public)),
+            },
+        ];
+        for message in messages {
+            let signed = SignedMessage::sign(&keypair, GAME, 0, message);
+            assert!(signed.verify().is_ok());
+
+            let from_json = SignedMessage::from_json(&signed.to_json()).unwrap();
+            assert_eq!(from_json, signed);
+            assert!(from_json.verify().is_ok());
+
+            let from_bytes = SignedMessage::from_bytes(&signed.to_bytes()).unwrap();
+            assert_eq!(from_bytes, signed);
+        }
+    }
+
+    #[test]
+    fn test_message_json_shape() {
+        let signed = SignedMessage::sign(
+            &fixed_keypair(),
+            GAME,
+            0,
+            Message::Action {
+                action: Action::Bet(20),
+            },
+        );
+        let json: serde_json::Value = serde_json::from_str(&signed.to_json()).unwrap();
+        assert_eq!(json["version"], WIRE_VERSION);
+        assert_eq!(json["game_id"], hex::encode(GAME));
+        assert_eq!(json["sequence"], 0);
+        assert_eq!(json["public"], fixed_keypair().public.to_hex());
+        assert_eq!(
+            json["message"],
+            serde_json::json!({"action": {"action": {"Bet": 20}}})
+        );
+        let card: Card = "Th".parse().unwrap();
+        assert_eq!(to_json(&card), "\"Th\"");
+    }
+
+    #[test]
+    fn test_tampered_message_is_rejected() {
+        let keypair = fixed_keypair();
+        let mut signed = SignedMessage::sign(
+            &keypair,
+            GAME,
+            0,
+            Message::Action {
+                action: Action::Call,
+            },
+        );
+        signed.message = Message::Action {
+            action: Action::Fold,
+        };
+        assert_eq!(signed.verify(), Err(WireError::BadSignature));
+
+        let mut impersonated = SignedMessage::sign(
+            &keypair,
+            GAME,
+            0,
+            Message::Action {
+                action: Action::Call,
+            },
+        );
+        impersonated.public = Player::new().public_key();
+        assert_eq!(impersonated.verify(), Err(WireError::BadSignature));
+
+        let mut resequenced = SignedMessage::sign(
+            &keypair,
+            GAME,
+            0,
+            Message::Action {
+                action: Action::Call,
+            },
+        );
+        resequenced.sequence = 1;
+        assert_eq!(resequenced.verify(), Err(WireError::BadSignature));
+        resequenced.sequence = 0;
+        resequenced.game_id = [4; 32];
+        assert_eq!(resequenced.verify(), Err(WireError::BadSignature));
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let mut signed = SignedMessage::sign(
+            &fixed_keypair(),
+            GAME,
+            0,
+            Message::Action {
+                action: Action::Check,
+            },
+        );
+        signed.version = WIRE_VERSION + 1;
+        assert_eq!(
+            signed.verify(),
+            Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+        assert_eq!(
+            SignedMessage::from_json(&signed.to_json()),
+            Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+        assert_eq!(
+            SignedMessage::from_bytes(&signed.to_bytes()),
+            Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+        assert!(matches!(
+            SignedMessage::from_json("{\"version\":1}"),
+            Err(WireError::InvalidEncoding(_))
+        ));
+    }
+}